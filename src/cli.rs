@@ -0,0 +1,66 @@
+use crate::utils;
+use std::env;
+
+/// Stores the options passed to remslice on the command line
+pub struct CliArgs {
+    /// A single command to run instead of starting the input loop (i.e. `remslice tda buy milk`)
+    pub command: Option<String>,
+    /// A file of commands to run instead of starting the input loop (`--script <path>`)
+    pub script: Option<String>,
+    /// Whether a script should keep running after a line fails (`--keep-going`/`-k`)
+    pub keep_going: bool,
+    /// Whether output should be JSON instead of text (`--json`)
+    pub json: bool,
+    /// The config file to use instead of the default one (`--config <path>`)
+    pub config: Option<String>,
+    /// Whether to check the config for problems and exit (`--check`)
+    pub check: bool,
+    /// The profile to start with (`--profile <name>`)
+    pub profile: Option<String>
+}
+
+impl CliArgs {
+    /// Parse the arguments the program was launched with
+    pub fn parse() -> Result<CliArgs, String> {
+        let mut res = CliArgs {
+            command: None,
+            script: None,
+            keep_going: false,
+            json: false,
+            config: None,
+            check: false,
+            profile: None
+        };
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--script" => {
+                    res.script = Some(args.next().ok_or("--script requires a file path")?);
+                },
+                "--keep-going" | "-k" => {
+                    res.keep_going = true;
+                },
+                "--json" => {
+                    res.json = true;
+                },
+                "--profile" => {
+                    res.profile = Some(args.next().ok_or("--profile requires a profile name")?);
+                },
+                "--check" => {
+                    res.check = true;
+                },
+                "--config" => {
+                    res.config = Some(args.next().ok_or("--config requires a file path")?);
+                },
+                _ => {
+                    // Everything from here on is the command itself, with each argument kept as one token as the
+                    // shell gave it, so that i.e. `remslice tda "call mom; ping"` doesn't run `ping`
+                    let rest: Vec<String> = std::iter::once(arg).chain(args).map(|arg| utils::quote_if_needed(&arg)).collect();
+                    res.command = Some(rest.join(" "));
+                    break;
+                }
+            }
+        }
+        Ok(res)
+    }
+}
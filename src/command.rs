@@ -1,5 +1,5 @@
 use crate::remstate;
-use crate::utils;
+use crate::utils::{ self, KeyMatch };
 
 pub enum CommandResult {
     Nominal,
@@ -7,6 +7,20 @@ pub enum CommandResult {
     EndProgram
 }
 
+/// The process exit code when a command could not be recognized at all
+pub const EXIT_CODE_UNRECOGNIZED: i32 = 3;
+
+impl CommandResult {
+    /// The process exit code corresponding to this result (used when running a single command)
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CommandResult::Nominal => 0,
+            CommandResult::Error(_) => 1,
+            CommandResult::EndProgram => 2
+        }
+    }
+}
+
 pub type CommandRunFn = fn(args: &Vec<String>, state: &mut remstate::RemState) -> CommandResult;
 
 pub enum ArgsLim {
@@ -15,15 +29,152 @@ pub enum ArgsLim {
     EndlessLastArg(i32),
     /// The number of arguments must be precisely this value
     Fixed(i32),
+    /// The number of arguments must be between these values (inclusive); any left out are given
+    /// the defaults from the command's params
+    Range(i32, i32),
+    /// There must be at least this many arguments, and any more are given to the command separately
+    /// (unlike `EndlessLastArg`, the extra arguments are still split and unquoted)
+    AtLeast(i32),
     /// There must be no arguments
     None
 }
 
+/// The type of value an argument must have
+pub enum ArgType {
+    /// An integer, optionally within an (inclusive) range
+    Int { min: Option<i64>, max: Option<i64> },
+    /// A decimal number
+    Float,
+    /// The ID of a todo displayed by the last `tdt`
+    TodoId,
+    /// The nickname of a tip
+    TipName,
+    /// The name of a shell alias
+    AliasName,
+    /// A yes/no setting (see `utils::parse_bool`)
+    Bool,
+    /// Any text
+    Text
+}
+
+impl ArgType {
+    /// Check that an argument has this type, returning a description of what it should be if not
+    fn validate(&self, arg: &str, state: &remstate::RemState) -> Result<(), String> {
+        match self {
+            ArgType::Int { min, max } => {
+                let in_range = arg.parse::<i64>().is_ok_and(|val| {
+                    min.is_none_or(|min| val >= min) && max.is_none_or(|max| val <= max)
+                });
+                match (in_range, min, max) {
+                    (true, _, _) => Ok(()),
+                    (_, Some(min), Some(max)) => Err(format!("an integer from {} to {}", min, max)),
+                    (_, Some(min), None) => Err(format!("an integer of at least {}", min)),
+                    (_, None, Some(max)) => Err(format!("an integer of at most {}", max)),
+                    (_, None, None) => Err("an integer".to_string())
+                }
+            },
+            ArgType::Float => {
+                arg.parse::<f32>().map(|_| ()).map_err(|_| "a number".to_string())
+            },
+            ArgType::TodoId => {
+                if state.todos_ids.contains_key(arg) {
+                    Ok(())
+                } else {
+                    Err("the ID of a todo displayed by the last `tdt`".to_string())
+                }
+            },
+            // Ambiguous names are left for the command to describe
+            ArgType::TipName => {
+                match state.config.get_tip_value(arg) {
+                    KeyMatch::Missing => Err("a tip nickname (see `tip-ls`)".to_string()),
+                    _ => Ok(())
+                }
+            },
+            ArgType::AliasName => {
+                match state.config.get_shell_alias(arg) {
+                    KeyMatch::Missing => Err("a shell alias (see `al-ls`)".to_string()),
+                    _ => Ok(())
+                }
+            },
+            ArgType::Bool => {
+                match utils::parse_bool(arg) {
+                    Some(_) => Ok(()),
+                    _ => Err("true or false".to_string())
+                }
+            },
+            ArgType::Text => Ok(())
+        }
+    }
+}
+
+/// Describes one argument that a command expects
+pub struct Param {
+    pub name: String,
+    pub arg_type: ArgType,
+    /// The value used if the argument is left out (None if the argument is required)
+    pub default: Option<String>
+}
+
+impl Param {
+    /// Make a param for an argument that must be given
+    pub fn required(name: &str, arg_type: ArgType) -> Param {
+        Param {
+            name: name.to_string(),
+            arg_type,
+            default: None
+        }
+    }
+
+    /// Make a param for an argument that may be left out, in which case the default is used
+    pub fn optional(name: &str, arg_type: ArgType, default: &str) -> Param {
+        Param {
+            name: name.to_string(),
+            arg_type,
+            default: Some(default.to_string())
+        }
+    }
+}
+
+/// The group a command is listed under by `help`
+#[derive(Clone, Copy, PartialEq)]
+pub enum Category {
+    System,
+    Misc,
+    Action,
+    Todos
+}
+
+impl Category {
+    /// Every category, in the order they are listed
+    pub const ALL: [Category; 4] = [Category::System, Category::Misc, Category::Action, Category::Todos];
+
+    /// Get the title displayed for this category
+    pub fn title(&self) -> &'static str {
+        match self {
+            Category::System => "System Commands",
+            Category::Misc => "Misc. Commands",
+            Category::Action => "Procedure/Action Commands",
+            Category::Todos => "Todo Commands"
+        }
+    }
+}
+
+/// Describes how to use a command, for `help`
+pub struct Help {
+    pub category: Category,
+    /// The command name and its arguments (i.e. "tdt {n}")
+    pub usage: String,
+    pub description: String
+}
+
 pub struct Command {
     names: Vec<String>,
     /// The number and structure of arguments that the command expects
     args_lim: ArgsLim,
-    pub run: CommandRunFn
+    pub run: CommandRunFn,
+    help: Option<Help>,
+    /// The type of each argument, checked before the command is run
+    params: Vec<Param>
 }
 
 impl Command {
@@ -31,8 +182,56 @@ impl Command {
         Command {
             names,
             args_lim,
-            run
+            run,
+            help: None,
+            params: Vec::new()
+        }
+    }
+
+    /// Add the types of the command's arguments, so they are checked before the command is run
+    pub fn params(mut self, params: Vec<Param>) -> Command {
+        self.params = params;
+        self
+    }
+
+    /// Fill in defaults for any arguments that were left out, then check that every argument has the right type
+    fn validate_args(&self, args: &mut Vec<String>, state: &remstate::RemState) -> Result<(), String> {
+        for param in self.params.iter().skip(args.len()) {
+            match &param.default {
+                Some(default) => args.push(default.clone()),
+                _ => return Err(format!("The argument `{}` is required", param.name))
+            }
+        }
+        for (arg, param) in args.iter().zip(&self.params) {
+            if let Err(expected) = param.arg_type.validate(arg, state) {
+                let usage = match &self.help {
+                    Some(help) => format!(" (usage: {})", help.usage),
+                    _ => String::new()
+                };
+                return Err(format!("The argument `{}` must be {}, but got `{}`{}", param.name, expected, arg, usage));
+            }
         }
+        Ok(())
+    }
+
+    /// Add help information to the command
+    pub fn help(mut self, category: Category, usage: &str, description: &str) -> Command {
+        self.help = Some(Help {
+            category,
+            usage: usage.to_string(),
+            description: description.to_string()
+        });
+        self
+    }
+
+    /// Get the help information about the command, if any
+    pub fn get_help(&self) -> Option<&Help> {
+        self.help.as_ref()
+    }
+
+    /// Get every name this command can be called by
+    pub fn names(&self) -> &Vec<String> {
+        &self.names
     }
 
     /// Whether a user's inputted command matches this command's structure
@@ -44,43 +243,58 @@ impl Command {
             ArgsLim::Fixed(needed_args) => {
                 num_args == needed_args
             },
+            ArgsLim::Range(min_args, max_args) => {
+                num_args >= min_args && num_args <= max_args
+            },
+            ArgsLim::AtLeast(needed_args) => {
+                num_args >= needed_args
+            },
             ArgsLim::None => {
                 num_args == 0
             }
         }
     }
 
-    /// Parse the input properly
-    // Ex. "mycommand A B Endless arg as str" -> ["A", "B", "Endless argument as one string"]
+    /// Parse the input properly (see `utils::tokenize` for how quotes and escapes are handled)
+    // Ex. "mycommand A "B C" Endless arg as str" -> ["A", "B C", "Endless arg as str"]
     /// This assumes that the command already matches (and thus will not check argument counts)
     pub fn parse_input(&self, full_input: &str) -> Vec<String> {
-        // TODO: impl ignoring spaces and keeping case within quotes, handling backslashes, etc.
+        let tokens = utils::tokenize(full_input);
         match self.args_lim {
             ArgsLim::EndlessLastArg(needed_args) => {
-                // Since spaces may be included in the final endless argument, we only want to split the start
-                // TODO: ignore case and trim for all args except the last one
-                full_input.splitn(needed_args as usize + 1, ' ').skip(1).map(|s| s.to_string()).collect()
+                // The final endless argument is the rest of the input exactly as typed, so spaces and quotes
+                // within it are kept (i.e. for shell commands), unless it is entirely quoted
+                let last_token = &tokens[(needed_args as usize).saturating_sub(1)];
+                let mut res: Vec<String> = tokens[1..needed_args as usize].iter().map(|t| t.text.clone()).collect();
+                res.push(utils::unquote_whole(full_input[last_token.end..].trim()));
+                res
             },
             _ => {
-                // TODO: ignore case and trim for all args except the last one
-                full_input.split(' ').skip(1).map(|s| s.to_string()).collect()
+                tokens.into_iter().skip(1).map(|t| t.text).collect()
             }
         }
     }
 }
 
+/// Find the command matching the user's input, if any
+pub fn find_command<'a>(full_input: &str, command_list: &'a [Command]) -> Option<&'a Command> {
+    let (num_args, command_name) = utils::process_input(full_input)?;
+    command_list.iter().find(|&x| x.matches(&command_name, num_args))
+}
+
 /// Find the matching command based on the user's parsed input and run it
 pub fn run_command(
     full_input: &str,
     state: &mut remstate::RemState,
     command_list: &[Command]
 ) -> Option<CommandResult> {
-    let (num_args, command_name) = utils::process_input(full_input)?;
-    let found = command_list.iter().find(|&x| x.matches(&command_name, num_args));
-    match found {
+    match find_command(full_input, command_list) {
         Some(command) => {
             // Run the command, parsing the input properly for the number of arguments
-            let parsed = command.parse_input(full_input);
+            let mut parsed = command.parse_input(full_input);
+            if let Err(descr) = command.validate_args(&mut parsed, state) {
+                return Some(CommandResult::Error(descr));
+            }
             let res = (command.run)(&parsed, state);
             Some(res)
         },
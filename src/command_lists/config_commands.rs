@@ -1,24 +1,54 @@
-use crate::command::{ ArgsLim, Command, CommandResult };
+use crate::command::{ ArgType, ArgsLim, Command, CommandResult, Param };
 use crate::utils;
 use std::sync::LazyLock;
 
 // Store these commands lazily so they are only accessed on the first call
 pub static CONFIG_COMMANDS: LazyLock<Vec<Command>> = LazyLock::new(|| {vec![
+    Command::new(
+        utils::string_vec!["include"], ArgsLim::EndlessLastArg(1),
+        |args, state| {
+            // The file is loaded by the config loader once this line is done
+            match state.config.expand(&args[0]) {
+                Ok(path) => {
+                    state.pending_include = Some(path);
+                    CommandResult::Nominal
+                },
+                Err(descr) => CommandResult::Error(descr)
+            }
+        }
+    ),
+    Command::new(
+        utils::string_vec!["set"], ArgsLim::EndlessLastArg(2),
+        |args, state| {
+            // Define a variable, which can use earlier variables
+            match state.config.expand(&args[1]) {
+                Ok(value) => {
+                    state.config.set_variable(&args[0], &value);
+                    CommandResult::Nominal
+                },
+                Err(descr) => CommandResult::Error(descr)
+            }
+        }
+    ),
     Command::new(
         utils::string_vec!["tip"], ArgsLim::EndlessLastArg(2),
         |args, state| {
             // Add a tip
-            let userpath = &args[1];
-            state.config.add_tip(args[0].trim(), userpath);
-            CommandResult::Nominal
+            match state.config.expand(&args[1]) {
+                Ok(userpath) => {
+                    state.config.add_tip(args[0].trim(), &userpath);
+                    CommandResult::Nominal
+                },
+                Err(descr) => CommandResult::Error(descr)
+            }
         }
     ),
     Command::new(
         utils::string_vec!["shell_alias"], ArgsLim::EndlessLastArg(2),
         |args, state| {
             // Add a shell alias
-            let usercommand = &args[1];
-            state.config.add_shell_alias(args[0].trim(), usercommand, false);
+            let usercommand = state.config.expand_shell_command(&args[1]);
+            state.config.add_shell_alias(args[0].trim(), &usercommand, false, false);
             CommandResult::Nominal
         }
     ),
@@ -26,8 +56,17 @@ pub static CONFIG_COMMANDS: LazyLock<Vec<Command>> = LazyLock::new(|| {vec![
         utils::string_vec!["shell_alias_quitting"], ArgsLim::EndlessLastArg(2),
         |args, state| {
             // Add a shell alias that quits after running
-            let usercommand = &args[1];
-            state.config.add_shell_alias(args[0].trim(), usercommand, true);
+            let usercommand = state.config.expand_shell_command(&args[1]);
+            state.config.add_shell_alias(args[0].trim(), &usercommand, true, false);
+            CommandResult::Nominal
+        }
+    ),
+    Command::new(
+        utils::string_vec!["shell_alias_wait"], ArgsLim::EndlessLastArg(2),
+        |args, state| {
+            // Add a shell alias that waits for the command to finish and displays its output
+            let usercommand = state.config.expand_shell_command(&args[1]);
+            state.config.add_shell_alias(args[0].trim(), &usercommand, false, true);
             CommandResult::Nominal
         }
     ),
@@ -43,15 +82,25 @@ pub static CONFIG_COMMANDS: LazyLock<Vec<Command>> = LazyLock::new(|| {vec![
     Command::new(
         utils::string_vec!["todo"], ArgsLim::EndlessLastArg(1),
         |args, state| {
-            state.config.todo_path = args[0].clone();
-            CommandResult::Nominal
+            match state.config.expand(&args[0]) {
+                Ok(path) => {
+                    state.config.set_todo_path(&path);
+                    CommandResult::Nominal
+                },
+                Err(descr) => CommandResult::Error(descr)
+            }
         }
     ),
     Command::new(
         utils::string_vec!["ted_command_prefix"], ArgsLim::EndlessLastArg(1),
         |args, state| {
-            state.config.ted_command_prefix = args[0].clone();
-            CommandResult::Nominal
+            match state.config.expand(&args[0]) {
+                Ok(prefix) => {
+                    state.config.ted_command_prefix = prefix;
+                    CommandResult::Nominal
+                },
+                Err(descr) => CommandResult::Error(descr)
+            }
         }
     ),
     Command::new(
@@ -71,18 +120,13 @@ pub static CONFIG_COMMANDS: LazyLock<Vec<Command>> = LazyLock::new(|| {vec![
     Command::new(
         utils::string_vec!["score_divby"], ArgsLim::Fixed(1),
         |args, state| {
-            let userdivby = &args[0];
-            match userdivby.parse::<f32>() {
-                Ok(res) => {
-                    state.config.score_divby = res;
-                    CommandResult::Nominal
-                },
-                _ => {
-                    CommandResult::Error("Could not parse score_divby".to_string())
-                }
-            }
+            // The argument has already been checked to be a number
+            state.config.score_divby = args[0].parse::<f32>().unwrap_or(state.config.score_divby);
+            CommandResult::Nominal
         }
-    ),
+    ).params(vec![
+        Param::required("divisor", ArgType::Float)
+    ]),
     Command::new(
         utils::string_vec!["score_formula_number"], ArgsLim::EndlessLastArg(1),
         |args, state| {
@@ -90,4 +134,34 @@ pub static CONFIG_COMMANDS: LazyLock<Vec<Command>> = LazyLock::new(|| {vec![
             CommandResult::Nominal
         }
     ),
+    Command::new(
+        utils::string_vec!["strict_matching"], ArgsLim::Fixed(1),
+        |args, state| {
+            // The argument has already been checked to be a yes/no setting
+            state.config.strict_matching = utils::parse_bool(&args[0]).unwrap_or(state.config.strict_matching);
+            CommandResult::Nominal
+        }
+    ).params(vec![
+        Param::required("enabled", ArgType::Bool)
+    ]),
+    Command::new(
+        utils::string_vec!["auto_reload"], ArgsLim::Fixed(1),
+        |args, state| {
+            // The argument has already been checked to be a yes/no setting
+            state.config.auto_reload = utils::parse_bool(&args[0]).unwrap_or(state.config.auto_reload);
+            CommandResult::Nominal
+        }
+    ).params(vec![
+        Param::required("enabled", ArgType::Bool)
+    ]),
+    Command::new(
+        utils::string_vec!["history_size"], ArgsLim::Fixed(1),
+        |args, state| {
+            // The argument has already been checked to be a non-negative integer
+            state.config.history_size = args[0].parse::<usize>().unwrap_or(state.config.history_size);
+            CommandResult::Nominal
+        }
+    ).params(vec![
+        Param::required("size", ArgType::Int { min: Some(0), max: None })
+    ]),
 ]});
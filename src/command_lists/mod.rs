@@ -9,3 +9,16 @@ pub fn get_rem_commands() -> &'static Vec<command::Command> {
 pub fn get_config_commands() -> &'static Vec<command::Command> {
     &config_commands::CONFIG_COMMANDS
 }
+
+/// Get every name of every rem command, without duplicates
+pub fn get_rem_command_names() -> Vec<String> {
+    let mut res: Vec<String> = Vec::new();
+    for command in get_rem_commands() {
+        for name in command.names() {
+            if !res.contains(name) {
+                res.push(name.clone());
+            }
+        }
+    }
+    res
+}
@@ -1,7 +1,9 @@
-use crate::command::{ ArgsLim, Command, CommandResult };
+use crate::command::{ ArgType, ArgsLim, Category, Command, CommandResult, Param };
+use crate::config::Config;
 use crate::utils;
 use crate::feature;
 use crate::remfetch;
+use crate::output;
 use std::sync::LazyLock;
 
 // Store these commands lazily so they are only accessed on the first call
@@ -9,67 +11,97 @@ pub static REM_COMMANDS: LazyLock<Vec<Command>> = LazyLock::new(|| {vec![
     Command::new(
         utils::string_vec!["score"], ArgsLim::None,
         |_args, state| {
-            feature::run_score(state);
-            CommandResult::Nominal
+            feature::run_score(state)
         }
+    ).help(
+        Category::Action, "score",
+        "generate a daily score based on input prompts defined in your config"
     ),
     Command::new(
         utils::string_vec!["version", "ver"], ArgsLim::None,
         |_args, state| {
-            println!("REMSLICE ({})", state.rem_data);
+            output::outln!(state.out, "REMSLICE ({})", state.rem_data);
             CommandResult::Nominal
         }
+    ).help(
+        Category::System, "version",
+        "display simple version information"
     ),
     Command::new(
         utils::string_vec!["remfetch"], ArgsLim::None,
         |_args, state| {
-            println!("{}", remfetch::remfetch(&state.rem_data));
+            let profile = state.profile.as_deref();
+            output::outln!(state.out, "{}", remfetch::remfetch(&state.rem_data, &state.config_location, profile));
+            state.out.set_data(remfetch::remfetch_data(&state.rem_data, &state.config_location, profile));
             CommandResult::Nominal
         }
+    ).help(
+        Category::System, "remfetch",
+        "aesthetically display more version information"
     ),
     Command::new(
         utils::string_vec!["bye"], ArgsLim::None,
-        |_args, _state| {
-            println!("bye!");
-            utils::await_enter();
+        |_args, state| {
+            output::outln!(state.out, "bye!");
+            utils::await_enter(&mut state.out);
             CommandResult::EndProgram
         }
+    ).help(
+        Category::Misc, "bye",
+        "exit with a farewell message"
     ),
     Command::new(
         utils::string_vec!["ping"], ArgsLim::None,
         |_args, state| {
             state.ping_count += 1;
-            println!("pong (x{})", state.ping_count);
+            output::outln!(state.out, "pong (x{})", state.ping_count);
             CommandResult::Nominal
         }
+    ).help(
+        Category::Misc, "ping",
+        "pong!"
     ),
     Command::new(
         utils::string_vec!["help"], ArgsLim::None,
-        |_args, _state| {
-            println!("A detailed list of all commands can be found in `README.md`;");
-            println!("please check it out for the features and cool stuff!");
-            println!("- `exit`/`quit`/`q` - exit immediately");
-            println!("- `version`/`ver` - display simple version information");
-            CommandResult::Nominal
+        |_args, state| {
+            feature::run_help(state)
         }
+    ).help(
+        Category::System, "help",
+        "list all commands, grouped by category"
+    ),
+    Command::new(
+        utils::string_vec!["help"], ArgsLim::Fixed(1),
+        |args, state| {
+            feature::run_help_command(state, &args[0])
+        }
+    ).help(
+        Category::System, "help {command}",
+        "display how to use a command"
     ),
     Command::new(
         utils::string_vec!["wipe", "clear"], ArgsLim::None,
-        |_args, _state| {
+        |_args, state| {
             // Print enough times that the screen gets filled
             for _i in 0..100 {
-                println!();
+                output::outln!(state.out);
             }
-            println!("The screen is clear!");
+            output::outln!(state.out, "The screen is clear!");
             CommandResult::Nominal
         }
+    ).help(
+        Category::Misc, "wipe",
+        "wipe the screen"
     ),
     Command::new(
         utils::string_vec!["pwd"], ArgsLim::None,
-        |_args, _state| {
-            println!("{}", utils::get_current_working_dir());
+        |_args, state| {
+            output::outln!(state.out, "{}", utils::get_current_working_dir());
             CommandResult::Nominal
         }
+    ).help(
+        Category::System, "pwd",
+        "display the current working directory"
     ),
     Command::new(
         utils::string_vec!["tip", "b"], ArgsLim::EndlessLastArg(2),
@@ -86,6 +118,12 @@ pub static REM_COMMANDS: LazyLock<Vec<Command>> = LazyLock::new(|| {vec![
                 }
             }
         }
+    ).params(vec![
+        Param::required("nickname", ArgType::TipName),
+        Param::required("grep prompt", ArgType::Text)
+    ]).help(
+        Category::Action, "tip {nickname} {grep prompt}",
+        "load a file by its tip nickname, then `grep` it for the prompt"
     ),
     Command::new(
         utils::string_vec!["tip", "b"], ArgsLim::EndlessLastArg(1),
@@ -97,19 +135,25 @@ pub static REM_COMMANDS: LazyLock<Vec<Command>> = LazyLock::new(|| {vec![
                     res
                 },
                 _ => {
-                    println!("Consider using `grep` or `print`");
+                    state.out.notice("Consider using `grep` or `print`");
                     res
                 }
             }
         }
+    ).params(vec![
+        Param::required("nickname", ArgType::TipName)
+    ]).help(
+        Category::Action, "tip {nickname}",
+        "load a file based on its defined tip nickname into the buffer"
     ),
     Command::new(
         utils::string_vec!["tip-ls"], ArgsLim::None,
         |_args, state| {
-            println!("All tips added:");
-            println!("{}", state.config.display_tips());
-            CommandResult::Nominal
+            feature::run_tip_ls(state)
         }
+    ).help(
+        Category::Action, "tip-ls",
+        "list all available tips and their file paths"
     ),
     Command::new(
         utils::string_vec!["grep"], ArgsLim::EndlessLastArg(1),
@@ -117,40 +161,41 @@ pub static REM_COMMANDS: LazyLock<Vec<Command>> = LazyLock::new(|| {vec![
             feature::run_grep(state, &args[0]);
             CommandResult::Nominal
         }
+    ).help(
+        Category::Action, "grep {query}",
+        "search the loaded file for lines containing the query (case-insensitive)"
     ),
     Command::new(
         utils::string_vec!["line"], ArgsLim::Fixed(1),
         |args, state| {
             feature::run_line(state, &args[0])
         }
+    ).params(vec![
+        Param::required("line number", ArgType::Int { min: Some(1), max: None })
+    ]).help(
+        Category::Action, "line {line number}",
+        "print the given line of the loaded file"
     ),
     Command::new(
         utils::string_vec!["tda"], ArgsLim::EndlessLastArg(1),
         |args, state| {
             feature::run_tda(state, &args[0])
         }
+    ).help(
+        Category::Todos, "tda {todo}",
+        "\"todo append\": add an entry to the todo file"
     ),
     Command::new(
-        utils::string_vec!["tdt"], ArgsLim::None,
-        |_args, state| {
-            feature::run_tdt(state, 1);
-            CommandResult::Nominal
-        }
-    ),
-    Command::new(
-        utils::string_vec!["tdt"], ArgsLim::Fixed(1),
+        utils::string_vec!["tdt"], ArgsLim::Range(0, 1),
         |args, state| {
-            // TODO: refactor this kind of check into something on the Command level?
-            // (i.e. every Command, in run, would type-check its argument)
-            match args[0].parse::<u32>() {
-                Ok(count) => {
-                    feature::run_tdt(state, count)
-                },
-                _ => {
-                    CommandResult::Error("Please enter a non-negative integer".to_string())
-                }
-            }
+            // The argument has already been checked to be a non-negative integer, so it only fails to parse if huge
+            feature::run_tdt(state, args[0].parse::<u32>().unwrap_or(u32::MAX))
         }
+    ).params(vec![
+        Param::optional("n", ArgType::Int { min: Some(0), max: None }, "1")
+    ]).help(
+        Category::Todos, "tdt [n]",
+        "\"todo top\": display the top todo entries (up until the nth most recent `##` header, default 1), with IDs"
     ),
     Command::new(
         utils::string_vec!["tdt2"], ArgsLim::None,
@@ -158,80 +203,139 @@ pub static REM_COMMANDS: LazyLock<Vec<Command>> = LazyLock::new(|| {vec![
             // A specific command name for backwards compatability only
             feature::run_tdt(state, 2)
         }
+    ).help(
+        Category::Todos, "tdt2",
+        "display the top todo entries up until the 2nd most recent `##` header"
     ),
     Command::new(
         utils::string_vec!["tdc"], ArgsLim::Fixed(1),
         |args, state| {
             feature::run_tdc(state, &args[0])
         }
+    ).params(vec![
+        Param::required("id", ArgType::TodoId)
+    ]).help(
+        Category::Todos, "tdc {id}",
+        "\"todo clear\": toggle the strikethrough for a todo by its ID (see `tdt`)"
     ),
     Command::new(
         utils::string_vec!["tde"], ArgsLim::EndlessLastArg(1),
         |args, state| {
             feature::run_tde(state, &args[0])
         }
+    ).help(
+        Category::Todos, "tde {todo}",
+        "\"todo edit\": replace the topmost todo entry, used for making a correction"
     ),
     Command::new(
         utils::string_vec!["tdae"], ArgsLim::EndlessLastArg(1),
         |args, state| {
             feature::run_tdae(state, &args[0])
         }
+    ).help(
+        Category::Todos, "tdae {text}",
+        "\"todo append-edit\": append text to the topmost todo entry"
     ),
     Command::new(
         utils::string_vec!["tdat"], ArgsLim::EndlessLastArg(1),
         |args, state| {
             feature::run_tdat(state, &args[0])
         }
+    ).help(
+        Category::Todos, "tdat {todo}",
+        "\"todo append-time\": add a todo entry prefaced by the current time"
     ),
     Command::new(
         utils::string_vec!["tdn"], ArgsLim::None,
         |_args, state| {
             feature::run_tdn(state)
         }
+    ).help(
+        Category::Todos, "tdn",
+        "\"todo new day\": insert the current date as a new `##` header in the todo file"
     ),
     Command::new(
         utils::string_vec!["ted"], ArgsLim::None,
         |_args, state| {
             feature::run_ted(state)
         }
+    ).help(
+        Category::Todos, "ted",
+        "\"todo editor\": open the todo file in the text editor from your config and exit"
     ),
     Command::new(
-        utils::string_vec!["al"], ArgsLim::Fixed(1),
+        utils::string_vec!["al"], ArgsLim::AtLeast(1),
         |args, state| {
             // Return the result from the alias, since aliases might be quitting
-            feature::run_al(state, &args[0])
+            feature::run_al(state, &args[0], &args[1..], false)
+        }
+    ).params(vec![
+        Param::required("shell alias", ArgType::AliasName)
+    ]).help(
+        Category::Action, "al {shell alias} [args...]",
+        "run the command defined by a shell alias in the config, with any arguments added to it"
+    ),
+    Command::new(
+        utils::string_vec!["al!"], ArgsLim::AtLeast(1),
+        |args, state| {
+            feature::run_al(state, &args[0], &args[1..], true)
         }
+    ).params(vec![
+        Param::required("shell alias", ArgType::AliasName)
+    ]).help(
+        Category::Action, "al! {shell alias} [args...]",
+        "run a shell alias, wait for it to finish, and show its output and exit code (the output can then be yanked)"
     ),
     Command::new(
         utils::string_vec!["al-ls"], ArgsLim::None,
         |_args, state| {
             feature::run_al_ls(state)
         }
+    ).help(
+        Category::Action, "al-ls",
+        "list all shell and rem aliases and what they refer to"
+    ),
+    Command::new(
+        utils::string_vec!["al-explain"], ArgsLim::Fixed(1),
+        |args, state| {
+            feature::run_al_explain(state, &args[0])
+        }
+    ).help(
+        Category::Action, "al-explain {rem alias}",
+        "show what a rem alias runs, following the rem aliases it runs down to the commands they finally run"
     ),
     Command::new(
         utils::string_vec!["print"], ArgsLim::None,
         |_args, state| {
             feature::run_print(state)
         }
+    ).help(
+        Category::Action, "print",
+        "print the entire loaded file"
     ),
     Command::new(
         utils::string_vec!["copy", "y"], ArgsLim::None,
         |_args, state| {
             utils::copy_to_clipboard(&state.to_copy_val);
+            // Use notices so that this message itself doesn't become the next thing to yank
             if state.to_copy_val.chars().count() > 6 {
-                println!("Yanked string starting with '{}'", &state.to_copy_val[..4]);
+                let start: String = state.to_copy_val.chars().take(4).collect();
+                state.out.notice(&format!("Yanked string starting with '{}'", start));
             } else {
-                println!("Yanked string '{}'", state.to_copy_val);
+                state.out.notice(&format!("Yanked string '{}'", state.to_copy_val));
             }
             CommandResult::Nominal
         }
+    ).help(
+        Category::Misc, "copy",
+        "copy (\"yank\") the output of the last command to the system clipboard"
     ),
     Command::new(
         utils::string_vec!["paste", "p"], ArgsLim::None,
-        |_args, _state| {
+        |_args, state| {
             match utils::paste_from_clipboard() {
                 Some(contents) => {
-                    println!("{}", contents);
+                    output::outln!(state.out, "{}", contents);
                     CommandResult::Nominal
                 },
                 _ => {
@@ -239,20 +343,191 @@ pub static REM_COMMANDS: LazyLock<Vec<Command>> = LazyLock::new(|| {vec![
                 }
             }
         }
+    ).help(
+        Category::Misc, "paste",
+        "display the contents of the system clipboard"
     ),
     Command::new(
         utils::string_vec!["q", "exit", "quit"], ArgsLim::None,
         |_args, _state| {
             CommandResult::EndProgram
         }
+    ).help(
+        Category::System, "q",
+        "exit immediately"
+    ),
+    Command::new(
+        utils::string_vec!["source"], ArgsLim::EndlessLastArg(1),
+        |args, state| {
+            // Run a file of commands, stopping at the first failure
+            feature::run_source(state, &args[0], true)
+        }
+    ).help(
+        Category::Action, "source {file path}",
+        "run each line of a script file as a command, stopping at the first failure"
+    ),
+    Command::new(
+        utils::string_vec!["source-k"], ArgsLim::EndlessLastArg(1),
+        |args, state| {
+            // Run a file of commands, keeping going after failures
+            feature::run_source(state, &args[0], false)
+        }
+    ).help(
+        Category::Action, "source-k {file path}",
+        "run each line of a script file as a command, continuing after failures"
+    ),
+    Command::new(
+        utils::string_vec!["reload"], ArgsLim::None,
+        |_args, state| {
+            feature::run_reload(state)
+        }
+    ).help(
+        Category::System, "reload",
+        "reload the config file and display what changed"
+    ),
+    Command::new(
+        utils::string_vec!["config-check"], ArgsLim::None,
+        |_args, state| {
+            feature::run_config_check(state)
+        }
+    ).help(
+        Category::System, "config-check",
+        "check the config file for problems, such as tips pointing to missing files and rem aliases that run each other forever"
+    ),
+    Command::new(
+        utils::string_vec!["tip-add"], ArgsLim::EndlessLastArg(2),
+        |args, state| {
+            let line = format!("tip {} {}", utils::quote_if_needed(&args[0]), args[1]);
+            feature::run_config_add_key(state, "tip", &args[0], Config::tip_keys, &format!("tip-add {}", args[0]), &line)
+        }
+    ).help(
+        Category::System, "tip-add {nickname} {file path}",
+        "add a tip to the config file"
+    ),
+    Command::new(
+        utils::string_vec!["tip-rm"], ArgsLim::Fixed(1),
+        |args, state| {
+            let tips = state.config.tip_origins();
+            feature::run_config_remove(state, "tip", &["tip"], &args[0], tips)
+        }
+    ).help(
+        Category::System, "tip-rm {nickname}",
+        "remove a tip from the config file it's defined in"
+    ),
+    Command::new(
+        utils::string_vec!["al-add"], ArgsLim::EndlessLastArg(2),
+        |args, state| {
+            let line = format!("shell_alias {} {}", utils::quote_if_needed(&args[0]), args[1]);
+            feature::run_config_add_key(state, "shell alias", &args[0], Config::shell_alias_keys, &format!("al-add {}", args[0]), &line)
+        }
+    ).help(
+        Category::System, "al-add {name} {shell command}",
+        "add a shell alias to the config file (quote the whole command if it contains `;`, `&&`, `||`, or `|`)"
+    ),
+    Command::new(
+        utils::string_vec!["al-rm"], ArgsLim::Fixed(1),
+        |args, state| {
+            let aliases = state.config.shell_alias_list().into_iter()
+                .map(|alias| (alias.key, alias.origin))
+                .collect();
+            feature::run_config_remove(state, "shell alias", &["shell_alias", "shell_alias_quitting", "shell_alias_wait"], &args[0], aliases)
+        }
+    ).help(
+        Category::System, "al-rm {name}",
+        "remove a shell alias from the config file it's defined in"
+    ),
+    Command::new(
+        utils::string_vec!["rem-alias-add"], ArgsLim::EndlessLastArg(2),
+        |args, state| {
+            let line = format!("rem_alias {} {}", utils::quote_if_needed(&args[0]), args[1]);
+            feature::run_config_add_key(state, "rem alias", &args[0], Config::rem_alias_keys, &format!("rem-alias-add {}", args[0]), &line)
+        }
+    ).help(
+        Category::System, "rem-alias-add {name} {rem command}",
+        "add a rem alias to the config file (quote the whole command if it contains `;`, `&&`, `||`, or `|`)"
+    ),
+    Command::new(
+        utils::string_vec!["score-p-add"], ArgsLim::EndlessLastArg(1),
+        |args, state| {
+            let line = format!("score_p {}", args[0]);
+            feature::run_config_add(state, "score-p-add", &line)
+        }
+    ).help(
+        Category::System, "score-p-add {prompt}",
+        "add a positive score prompt to the config file"
+    ),
+    Command::new(
+        utils::string_vec!["config-export"], ArgsLim::Range(2, 3),
+        |args, state| {
+            if args[0] != "--format" {
+                return CommandResult::Error("Usage: config-export --format {toml|json} [file path]".to_string());
+            }
+            feature::run_config_export(state, &args[1], args.get(2).map(|path| path.as_str()))
+        }
+    ).help(
+        Category::System, "config-export --format {toml|json} [file path]",
+        "write the fully loaded config as TOML or JSON, to a file if one is given (the file can be used as a config)"
+    ),
+    Command::new(
+        utils::string_vec!["config-undo"], ArgsLim::None,
+        |_args, state| {
+            feature::run_config_undo(state)
+        }
+    ).help(
+        Category::System, "config-undo",
+        "undo the last change made to the config files by a command such as `tip-add` or `tip-rm`"
+    ),
+    Command::new(
+        utils::string_vec!["profile"], ArgsLim::None,
+        |_args, state| {
+            feature::run_profile_ls(state)
+        }
+    ).help(
+        Category::System, "profile",
+        "display the active profile and every profile in the config"
+    ),
+    Command::new(
+        utils::string_vec!["profile"], ArgsLim::Fixed(1),
+        |args, state| {
+            feature::run_profile(state, &args[0])
+        }
+    ).help(
+        Category::System, "profile {name}",
+        "switch to a profile, so the config's `[profile=name]` sections apply (`profile none` uses no profile)"
+    ),
+    Command::new(
+        utils::string_vec!["format"], ArgsLim::None,
+        |_args, state| {
+            let format = match state.out.format() {
+                output::OutputFormat::Text => "text",
+                output::OutputFormat::Json => "json"
+            };
+            output::outln!(state.out, "The output format is {}", format);
+            CommandResult::Nominal
+        }
+    ).help(
+        Category::System, "format",
+        "display the current output format"
+    ),
+    Command::new(
+        utils::string_vec!["format"], ArgsLim::Fixed(1),
+        |args, state| {
+            feature::run_format(state, &args[0])
+        }
+    ).help(
+        Category::System, "format {text|json}",
+        "set the output format"
     ),
     Command::new(
         utils::string_vec!["time"], ArgsLim::None,
         |_args, state| {
             let output = utils::get_date_time_formatted();
-            state.to_copy_val = output.clone();
-            println!("{}", output);
+            state.out.set_copy_val(&output);
+            output::outln!(state.out, "{}", output);
             CommandResult::Nominal
         }
+    ).help(
+        Category::Misc, "time",
+        "display the current date and time"
     ),
 ]});
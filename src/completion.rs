@@ -0,0 +1,94 @@
+use crate::command_lists;
+use crate::remstate::RemState;
+use rustyline::completion::{ Completer, Pair };
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{ Context, Helper };
+
+/// Completes command names and arguments on Tab, based on a snapshot of the current state
+pub struct RemCompleter {
+    command_names: Vec<String>,
+    tip_keys: Vec<String>,
+    shell_alias_keys: Vec<String>,
+    rem_alias_keys: Vec<String>,
+    todo_ids: Vec<String>,
+    profile_names: Vec<String>
+}
+
+impl RemCompleter {
+    pub fn new() -> RemCompleter {
+        RemCompleter {
+            command_names: command_lists::get_rem_command_names(),
+            tip_keys: Vec::new(),
+            shell_alias_keys: Vec::new(),
+            rem_alias_keys: Vec::new(),
+            todo_ids: Vec::new(),
+            profile_names: Vec::new()
+        }
+    }
+
+    /// Take a new snapshot of the completable data (i.e. tips and todo IDs) from the state
+    pub fn update(&mut self, state: &RemState) {
+        self.tip_keys = state.config.tip_keys();
+        self.shell_alias_keys = state.config.shell_alias_keys();
+        self.rem_alias_keys = state.config.rem_alias_keys();
+        self.profile_names = state.profile_names.clone();
+        let mut todo_ids: Vec<&String> = state.todos_ids.keys().collect();
+        // Shorter IDs first, so "b" comes before "aa"
+        todo_ids.sort_by_key(|id| (id.len(), id.to_string()));
+        self.todo_ids = todo_ids.into_iter().cloned().collect();
+    }
+
+    /// Get the possible values for an argument of a command
+    /// `arg_index` is the index of the argument being typed (0 is the first argument after the command name)
+    fn argument_candidates(&self, command_name: &str, arg_index: usize) -> Vec<&String> {
+        match (command_name, arg_index) {
+            ("tip" | "b", 0) => self.tip_keys.iter().collect(),
+            ("al" | "al!", 0) => self.shell_alias_keys.iter().collect(),
+            ("tdc", 0) => self.todo_ids.iter().collect(),
+            ("help", 0) => self.command_names.iter().collect(),
+            ("profile", 0) => self.profile_names.iter().collect(),
+            _ => Vec::new()
+        }
+    }
+}
+
+impl Completer for RemCompleter {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let before_cursor = &line[..pos];
+        // The word being completed starts after the last space
+        let start = before_cursor.rfind(' ').map(|i| i + 1).unwrap_or(0);
+        let word = &before_cursor[start..];
+        let previous_words: Vec<&str> = before_cursor[..start].split_whitespace().collect();
+        let candidates: Vec<&String> = match previous_words.split_first() {
+            None => {
+                // Completing the command name itself
+                self.command_names.iter().chain(self.rem_alias_keys.iter()).collect()
+            },
+            Some((command_name, args)) => {
+                self.argument_candidates(command_name, args.len())
+            }
+        };
+        let matches = candidates.into_iter()
+            .filter(|candidate| candidate.starts_with(word))
+            .map(|candidate| Pair {
+                display: candidate.clone(),
+                replacement: format!("{} ", candidate)
+            })
+            .collect();
+        Ok((start, matches))
+    }
+}
+
+impl Hinter for RemCompleter {
+    type Hint = String;
+}
+
+impl Highlighter for RemCompleter {}
+
+impl Validator for RemCompleter {}
+
+impl Helper for RemCompleter {}
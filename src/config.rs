@@ -1,27 +1,109 @@
+use crate::command::{ self, Command };
+use crate::utils::{ self, KeyMatch };
+
+/// Where the path of the config file came from
+#[derive(Clone, Copy)]
+pub enum ConfigSource {
+    /// The `--config` command line flag
+    Flag,
+    /// The `REMSLICE_CONFIG` environment variable
+    EnvVar,
+    /// `$XDG_CONFIG_HOME/remslice/remrc`, which is only used if it exists
+    Xdg,
+    /// `~/.remrc`
+    Home
+}
+
+impl ConfigSource {
+    /// Describe the source, i.e. for `remfetch`
+    pub fn describe(&self) -> &'static str {
+        match self {
+            ConfigSource::Flag => "--config flag",
+            ConfigSource::EnvVar => "REMSLICE_CONFIG",
+            ConfigSource::Xdg => "XDG config directory",
+            ConfigSource::Home => "home directory"
+        }
+    }
+}
+
+/// The path of the config file and where it came from
+#[derive(Clone)]
+pub struct ConfigLocation {
+    pub path: String,
+    pub source: ConfigSource
+}
+
+impl ConfigLocation {
+    /// Find the config file, preferring (in order) the `--config` flag's path, the `REMSLICE_CONFIG` environment
+    /// variable, `$XDG_CONFIG_HOME/remslice/remrc` if it exists, and `~/.remrc`
+    pub fn resolve(flag_path: Option<String>) -> ConfigLocation {
+        if let Some(path) = flag_path {
+            return ConfigLocation { path, source: ConfigSource::Flag };
+        }
+        match std::env::var("REMSLICE_CONFIG") {
+            Ok(path) if !path.is_empty() => {
+                return ConfigLocation { path, source: ConfigSource::EnvVar };
+            },
+            _ => ()
+        }
+        match utils::get_xdg_config_path() {
+            Some(path) if std::path::Path::new(&path).is_file() => {
+                ConfigLocation { path, source: ConfigSource::Xdg }
+            },
+            _ => {
+                ConfigLocation { path: utils::get_home_config_path(), source: ConfigSource::Home }
+            }
+        }
+    }
+
+    /// Whether the config file was chosen explicitly (so it not existing is worth mentioning)
+    pub fn is_explicit(&self) -> bool {
+        matches!(self.source, ConfigSource::Flag | ConfigSource::EnvVar)
+    }
+}
+
 /// Stores a tip key/value pair
 struct Pair {
     key: String,
-    value: String
+    value: String,
+    /// Where the pair was defined (see `Config::set_origin`)
+    origin: String
 }
 
 #[derive(Clone)]
 pub struct ShellAlias {
     pub key: String,
     pub command: String,
-    pub quit_after_running: bool
+    pub quit_after_running: bool,
+    /// Whether remslice waits for the command to finish and displays its output and exit code
+    pub wait: bool,
+    /// Where the alias was defined (see `Config::set_origin`)
+    pub origin: String
 }
 
 /// Stores a rem config based on the remrc file
 pub struct Config {
     tips: Vec<Pair>,
+    /// Variables defined with `set`, for use in other config values
+    variables: Vec<Pair>,
     shell_aliases: Vec<ShellAlias>,
     rem_aliases: Vec<Pair>,
     pub todo_path: String,
+    /// Where the todo path was set (empty if it's the default)
+    todo_origin: String,
     score_positive: Vec<String>,
     score_negative: Vec<String>,
     pub score_divby: f32,
     pub score_formula_number: String,
     pub ted_command_prefix: String,
+    /// The maximum number of lines kept in the input history file
+    pub history_size: usize,
+    /// Whether commands, tips, and aliases must be typed exactly (rather than abbreviated or differently capitalized)
+    pub strict_matching: bool,
+    /// Whether the config is reloaded before each prompt if the config file has been modified
+    pub auto_reload: bool,
+    /// Where the config line being loaded is (i.e. "/home/me/.remrc:12"), recorded on each tip and alias added
+    current_origin: String,
 }
 
 impl Config {
@@ -29,36 +111,87 @@ impl Config {
     pub fn new() -> Config {
         Config {
             tips: Vec::new(),
+            variables: Vec::new(),
             shell_aliases: Vec::new(),
             rem_aliases: Vec::new(),
             todo_path: "default_todos.md".to_string(),
+            todo_origin: String::new(),
             score_positive: Vec::new(),
             score_negative: Vec::new(),
             score_divby: 5.0,
             score_formula_number: "1".to_string(),
             ted_command_prefix: "gvim +".to_string(),
+            history_size: 1000,
+            strict_matching: false,
+            auto_reload: false,
+            current_origin: String::new(),
         }
     }
 
+    /// Set where the config line being loaded is, so it can be pointed to later (i.e. by `config-check`)
+    pub fn set_origin(&mut self, origin: &str) {
+        self.current_origin = origin.to_string();
+    }
+
     pub fn add_tip(&mut self, key: &str, value: &str) {
         self.tips.push(Pair {
             key: key.to_string(),
-            value: value.to_string()
+            value: value.to_string(),
+            origin: self.current_origin.clone()
         });
     }
 
-    pub fn add_shell_alias(&mut self, key: &str, command: &str, quit_after_running: bool) {
+    /// Set the path of the todo file
+    pub fn set_todo_path(&mut self, path: &str) {
+        self.todo_path = path.to_string();
+        self.todo_origin = self.current_origin.clone();
+    }
+
+    /// Get where the todo path was set (None if it's the default)
+    pub fn todo_origin(&self) -> Option<String> {
+        Some(self.todo_origin.clone()).filter(|origin| !origin.is_empty())
+    }
+
+    /// Define a variable, replacing any existing one with the same name
+    pub fn set_variable(&mut self, name: &str, value: &str) {
+        self.variables.retain(|var| var.key != name);
+        self.variables.push(Pair {
+            key: name.to_string(),
+            value: value.to_string(),
+            origin: self.current_origin.clone()
+        });
+    }
+
+    /// Expand the variables, environment variables, and `~` in a config value (see `utils::expand_variables`)
+    pub fn expand(&self, value: &str) -> Result<String, String> {
+        utils::expand_variables(value, |name| self.get_variable(name), false)
+    }
+
+    /// Expand the variables and `~` in a shell command, leaving environment variables and other shell syntax
+    /// (i.e. `${f}` in a loop) for the shell (see `utils::expand_variables`)
+    pub fn expand_shell_command(&self, command: &str) -> String {
+        utils::expand_variables(command, |name| self.get_variable(name), true).unwrap_or(command.to_string())
+    }
+
+    fn get_variable(&self, name: &str) -> Option<String> {
+        self.variables.iter().find(|var| var.key == name).map(|var| var.value.clone())
+    }
+
+    pub fn add_shell_alias(&mut self, key: &str, command: &str, quit_after_running: bool, wait: bool) {
         self.shell_aliases.push(ShellAlias {
             key: key.to_string(),
             command: command.to_string(),
-            quit_after_running
+            quit_after_running,
+            wait,
+            origin: self.current_origin.clone()
         });
     }
 
     pub fn add_rem_alias(&mut self, key: &str, value: &str) {
         self.rem_aliases.push(Pair {
             key: key.to_string(),
-            value: value.to_string()
+            value: value.to_string(),
+            origin: self.current_origin.clone()
         });
     }
 
@@ -70,10 +203,25 @@ impl Config {
         }
     }
 
-    /// Get the value of a tip starting with a key
-    pub fn get_tip_value(&self, start: &str) -> Option<String> {
-        self.tips.iter().find(|tip| tip.key.starts_with(start))
-            .map(|tip| tip.value.clone())
+    /// Get the value of the tip matching a key (see `utils::match_key`)
+    pub fn get_tip_value(&self, search_for: &str) -> KeyMatch<String> {
+        utils::match_key(&self.tip_keys(), search_for, self.strict_matching)
+            .map(|i| self.tips[i].value.clone())
+    }
+
+    /// Get the key and value of every tip
+    pub fn tip_list(&self) -> Vec<(String, String)> {
+        self.tips.iter().map(|tip| (tip.key.clone(), tip.value.clone())).collect()
+    }
+
+    /// Get the key and origin of every tip
+    pub fn tip_origins(&self) -> Vec<(String, String)> {
+        self.tips.iter().map(|tip| (tip.key.clone(), tip.origin.clone())).collect()
+    }
+
+    /// Get the keys of all tips
+    pub fn tip_keys(&self) -> Vec<String> {
+        self.tips.iter().map(|tip| tip.key.clone()).collect()
     }
 
     /// Display all tips
@@ -85,9 +233,10 @@ impl Config {
         res
     }
 
-    /// Get the information about a shell alias matching a key
-    pub fn get_shell_alias(&self, search_for: &str) -> Option<ShellAlias> {
-        self.shell_aliases.iter().find(|alias| alias.key == search_for).cloned()
+    /// Get the information about the shell alias matching a key (see `utils::match_key`)
+    pub fn get_shell_alias(&self, search_for: &str) -> KeyMatch<ShellAlias> {
+        utils::match_key(&self.shell_alias_keys(), search_for, self.strict_matching)
+            .map(|i| self.shell_aliases[i].clone())
     }
 
     /// Get the value of a rem alias matching a key
@@ -96,14 +245,95 @@ impl Config {
             .map(|alias| alias.value.clone())
     }
 
+    /// Get every shell alias
+    pub fn shell_alias_list(&self) -> Vec<ShellAlias> {
+        self.shell_aliases.clone()
+    }
+
+    /// Get the key and value of every rem alias
+    pub fn rem_alias_list(&self) -> Vec<(String, String)> {
+        self.rem_aliases.iter().map(|alias| (alias.key.clone(), alias.value.clone())).collect()
+    }
+
+    /// Get the key and origin of every rem alias
+    pub fn rem_alias_origins(&self) -> Vec<(String, String)> {
+        self.rem_aliases.iter().map(|alias| (alias.key.clone(), alias.origin.clone())).collect()
+    }
+
+    /// Find every cycle of rem aliases that run each other (i.e. ["a", "b", "a"]), each listed once
+    /// Commands that match a built-in command (including its number of arguments) are skipped, since the built-in
+    /// command is run instead of a rem alias with the same name
+    pub fn find_rem_alias_cycles(&self, commands: &[Command]) -> Vec<Vec<String>> {
+        let mut cycles: Vec<Vec<String>> = Vec::new();
+        let mut keys = self.rem_alias_keys();
+        keys.sort();
+        keys.dedup();
+        for key in keys {
+            let mut path = vec![key];
+            self.follow_rem_alias(&mut path, commands, &mut cycles);
+        }
+        cycles
+    }
+
+    /// Depth-first search from the last rem alias in `path` for paths leading back to its first alias
+    fn follow_rem_alias(&self, path: &mut Vec<String>, commands: &[Command], cycles: &mut Vec<Vec<String>>) {
+        let value = match self.get_rem_alias_value(path.last().unwrap()) {
+            Some(value) => value,
+            _ => return
+        };
+        for stage in Self::commands_run_by(&value) {
+            if command::find_command(&stage, commands).is_some() {
+                continue;
+            }
+            let name = Self::command_name(&stage);
+            if name == path[0] {
+                // Only list each cycle once, starting from its alphabetically first alias
+                if path.iter().all(|key| *key >= path[0]) {
+                    let mut cycle = path.clone();
+                    cycle.push(name);
+                    cycles.push(cycle);
+                }
+            } else if !path.contains(&name) && self.get_rem_alias_value(&name).is_some() {
+                path.push(name);
+                self.follow_rem_alias(path, commands, cycles);
+                path.pop();
+            }
+        }
+    }
+
+    /// Get every single command run by a line of input (i.e. ["tdn", "tda standup"] for "tdn; tda standup")
+    pub fn commands_run_by(input: &str) -> Vec<String> {
+        let mut res = Vec::new();
+        for (_, part) in utils::split_command_chain(input) {
+            res.extend(utils::split_pipeline(&part).into_iter().filter(|stage| !stage.is_empty()));
+        }
+        res
+    }
+
+    /// Get the name a single command starts with (i.e. "tda" for "tda standup")
+    pub fn command_name(input: &str) -> String {
+        utils::tokenize(input).into_iter().next().map(|token| token.text).unwrap_or_default()
+    }
+
+    /// Get the keys of all shell aliases
+    pub fn shell_alias_keys(&self) -> Vec<String> {
+        self.shell_aliases.iter().map(|alias| alias.key.clone()).collect()
+    }
+
+    /// Get the keys of all rem aliases
+    pub fn rem_alias_keys(&self) -> Vec<String> {
+        self.rem_aliases.iter().map(|alias| alias.key.clone()).collect()
+    }
+
     /// Display all shell aliases
     pub fn display_shell_aliases(&self) -> String {
         let mut res = String::new();
         for alias in &self.shell_aliases {
             res.push_str(&format!(
-                "   {}{} : {}\n",
+                "   {}{}{} : {}\n",
                 alias.key,
                 if alias.quit_after_running { " (Q)" } else { "" },
+                if alias.wait { " (W)" } else { "" },
                 alias.command
             ));
         }
@@ -0,0 +1,172 @@
+use crate::remstate::RemState;
+use crate::utils;
+use std::fs;
+
+/// One line added to or removed from a config file
+enum LineChange {
+    /// A line was added at an index
+    Added { index: usize, line: String },
+    /// A line was removed from an index, after the line that was before it (None if it was the first line)
+    Removed { index: usize, line: String, before: Option<String> },
+    /// A line ending was added to the last line of a file, at an index, so that a line could be added after it
+    EndingAdded { index: usize, line: String }
+}
+
+/// A change made to config files by a command (i.e. `tip-add`), which can be undone with `config-undo`
+/// Only the lines the change touched are recorded, so undoing it keeps any other changes made to the files since
+pub struct ConfigEdit {
+    /// What the change was (i.e. "tip-add notes")
+    pub description: String,
+    /// Every line added or removed, in the order it happened, with the path of its file
+    changes: Vec<(String, LineChange)>,
+    /// The files that didn't exist before this change
+    created: Vec<String>
+}
+
+impl ConfigEdit {
+    pub fn new(description: &str) -> ConfigEdit {
+        ConfigEdit {
+            description: description.to_string(),
+            changes: Vec::new(),
+            created: Vec::new()
+        }
+    }
+
+    /// Add a line to the end of a config file, keeping everything else as it is
+    /// If the file ends inside a section (i.e. `[os=windows]`), an `[end]` line is added first so the new line
+    /// always applies
+    pub fn append_line(&mut self, path: &str, line: &str) -> Result<(), String> {
+        let contents = utils::read_file(path);
+        if contents.is_none() {
+            self.created.push(path.to_string());
+        }
+        let mut lines = split_lines(&contents.unwrap_or_default());
+        let last_index = lines.len().wrapping_sub(1);
+        if let Some(last) = lines.last_mut().filter(|last| !last.ends_with('\n')) {
+            self.changes.push((path.to_string(), LineChange::EndingAdded { index: last_index, line: last.clone() }));
+            last.push('\n');
+        }
+        let last_header = lines.iter().rev().find_map(|line| RemState::parse_section_header(line));
+        if last_header.is_some_and(|header| header != "end") {
+            self.add_line(path, &mut lines, "[end]");
+        }
+        self.add_line(path, &mut lines, line);
+        write_lines(path, &lines)
+    }
+
+    fn add_line(&mut self, path: &str, lines: &mut Vec<String>, line: &str) {
+        self.changes.push((path.to_string(), LineChange::Added { index: lines.len(), line: line.to_string() }));
+        lines.push(format!("{}\n", line));
+    }
+
+    /// Remove lines (by their NUMBER, not index) from a config file, keeping everything else as it is
+    pub fn remove_lines(&mut self, path: &str, line_numbers: &[usize]) -> Result<(), String> {
+        let contents = utils::read_file(path).ok_or(format!("The config file at {} could not be read", path))?;
+        let mut lines = split_lines(&contents);
+        let mut line_numbers = line_numbers.to_vec();
+        line_numbers.sort();
+        line_numbers.dedup();
+        // Remove the last line first, so that each removal's index is still right when it is undone in reverse
+        for index in line_numbers.into_iter().rev().filter_map(|line_number| line_number.checked_sub(1)) {
+            if index >= lines.len() {
+                continue;
+            }
+            let line = lines.remove(index);
+            let before = index.checked_sub(1).map(|i| lines[i].clone());
+            self.changes.push((path.to_string(), LineChange::Removed { index, line, before }));
+        }
+        write_lines(path, &lines)
+    }
+
+    /// Reverse every line this edit added or removed, as long as the lines around them haven't changed since
+    /// Nothing is written unless the whole edit can be undone
+    pub fn undo(&self) -> Result<(), String> {
+        let mut files: Vec<(String, Vec<String>)> = Vec::new();
+        for (path, change) in self.changes.iter().rev() {
+            let i = match files.iter().position(|(other, _)| other == path) {
+                Some(i) => i,
+                _ => {
+                    files.push((path.clone(), split_lines(&utils::read_file(path).unwrap_or_default())));
+                    files.len() - 1
+                }
+            };
+            let lines = &mut files[i].1;
+            let (index, unchanged) = match change {
+                LineChange::Added { index, line } => {
+                    (*index, lines.get(*index).is_some_and(|current| strip_line_ending(current) == line))
+                },
+                LineChange::Removed { index, before, .. } => {
+                    let current_before = index.checked_sub(1).and_then(|i| lines.get(i)).map(|line| strip_line_ending(line));
+                    (*index, *index <= lines.len() && current_before == before.as_deref().map(strip_line_ending))
+                },
+                LineChange::EndingAdded { index, line } => {
+                    // The ending can only be taken away again if the line is still the last one
+                    let current = lines.get(*index).filter(|_| *index + 1 == lines.len());
+                    (*index, current.is_some_and(|current| current.ends_with('\n') && strip_line_ending(current) == line))
+                }
+            };
+            if !unchanged {
+                return Err(format!(
+                    "The config file at {} was changed around line {} since `{}`, so it can't be undone", path, index + 1, self.description
+                ));
+            }
+            match change {
+                LineChange::Added { index, .. } => {
+                    lines.remove(*index);
+                },
+                LineChange::Removed { index, line, .. } => {
+                    let mut line = line.clone();
+                    if !line.ends_with('\n') && *index < lines.len() {
+                        line.push('\n');
+                    }
+                    lines.insert(*index, line);
+                },
+                LineChange::EndingAdded { index, line } => {
+                    lines[*index] = line.clone();
+                }
+            }
+        }
+        for (path, lines) in &files {
+            if lines.is_empty() && self.created.contains(path) {
+                fs::remove_file(path).map_err(|_| format!("The config file at {} could not be removed", path))?;
+            } else {
+                write_lines(path, lines)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Split the contents of a file into lines that each keep their own line ending
+fn split_lines(contents: &str) -> Vec<String> {
+    contents.split_inclusive('\n').map(|line| line.to_string()).collect()
+}
+
+fn strip_line_ending(line: &str) -> &str {
+    line.trim_end_matches(['\r', '\n'])
+}
+
+fn write_lines(path: &str, lines: &[String]) -> Result<(), String> {
+    if utils::write_to_file(path, &lines.concat()) {
+        Ok(())
+    } else {
+        Err(format!("The config file at {} could not be written to", path))
+    }
+}
+
+/// Split where a config line is (i.e. "/home/me/.remrc:12") into the file path and line number
+pub fn parse_origin(origin: &str) -> Option<(String, usize)> {
+    let (path, line_number) = origin.rsplit_once(':')?;
+    Some((path.to_string(), line_number.parse().ok()?))
+}
+
+/// Whether a line of a config file is still one of the config commands defining a key (i.e. `tip notes ...`),
+/// in case the file was changed since it was loaded
+pub fn line_defines(path: &str, line_number: usize, config_commands: &[&str], key: &str) -> bool {
+    let contents = utils::read_file(path).unwrap_or_default();
+    let tokens = match contents.lines().nth(line_number.wrapping_sub(1)) {
+        Some(line) => utils::tokenize(line),
+        _ => return false
+    };
+    tokens.len() >= 2 && config_commands.contains(&tokens[0].text.as_str()) && tokens[1].text.trim() == key
+}
@@ -1,48 +1,74 @@
 use crate::remstate;
-use crate::utils;
-use crate::command::CommandResult;
+use crate::utils::{ self, KeyMatch };
+use crate::command::{ self, Category, CommandResult };
+use crate::command_lists;
+use crate::script::Script;
+use crate::output;
+use crate::config::Config;
+use crate::config_edit::{ self, ConfigEdit };
+use crate::structured_config::{ StructuredConfig, StructuredFormat };
+use serde_json::json;
 
-pub fn run_score(state: &mut remstate::RemState) {
+pub fn run_score(state: &mut remstate::RemState) -> CommandResult {
     // Get based on config
     let divide_by: f32 = state.config.score_divby;
     let formula_number: &str = &state.config.score_formula_number;
     // Obtain relevant information
-    println!("Today's questions:");
+    state.out.notice("Today's questions:");
     let mut daily_score_disp = format!("Daily Score (Formula {}) = (", formula_number);
     let mut total_score: f32 = 0.0;
+    let mut answers = Vec::new();
     for cat in state.config.score_positive() {
-        println!("{}", cat);
-        let uin = utils::get_user_input_decimal(0.0, 1.0);
+        state.out.notice(&cat);
+        let uin = match utils::get_user_input_decimal(&mut state.out, 0.0, 1.0) {
+            Some(uin) => uin,
+            _ => return CommandResult::Error("The input ended before the score was complete".to_string())
+        };
         total_score += uin;
         daily_score_disp.push_str(&format!(" + {:.2}", uin));
+        answers.push(json!({ "prompt": cat, "positive": true, "value": uin }));
     }
     for cat in state.config.score_negative() {
-        println!("{}", cat);
-        let uin = utils::get_user_input_decimal(0.0, 1.0);
+        state.out.notice(&cat);
+        let uin = match utils::get_user_input_decimal(&mut state.out, 0.0, 1.0) {
+            Some(uin) => uin,
+            _ => return CommandResult::Error("The input ended before the score was complete".to_string())
+        };
         total_score -= uin;
         daily_score_disp.push_str(&format!(" - {:.2}", uin));
+        answers.push(json!({ "prompt": cat, "positive": false, "value": uin }));
     }
     // Calculate and format
     total_score /= divide_by;
     daily_score_disp.push_str(&format!(") / {} = {:.2}", divide_by, total_score));
-    state.to_copy_val = daily_score_disp.clone();
+    state.out.set_copy_val(&daily_score_disp);
+    state.out.set_data(json!({
+        "formula": formula_number,
+        "answers": answers,
+        "divided_by": divide_by,
+        // Rounded like the report, since f32 values gain noise when converted
+        "score": (total_score as f64 * 100.0).round() / 100.0,
+        "report": daily_score_disp
+    }));
     // Create the score report
-    println!("Today's daily score:");
-    println!("{}", daily_score_disp);
+    output::outln!(state.out, "Today's daily score:");
+    output::outln!(state.out, "{}", daily_score_disp);
     // Options: copy, continue, restart, edit
-    println!("To copy the report, enter `copy`");
+    output::outln!(state.out, "To copy the report, enter `copy`");
+    CommandResult::Nominal
 }
 
 pub fn run_tip(state: &mut remstate::RemState, key: &str) -> CommandResult {
     // Search for the given file and display it, so a tip can be found
     match state.config.get_tip_value(key) {
-        Some(tip_value) => {
+        KeyMatch::Found(tip_value) => {
             // Open and load the file, if possible
             match utils::read_file(&tip_value) {
                 Some(thecontents) => {
                     // Load the file
                     state.file_loaded = thecontents.clone();
-                    println!("The file at {} is loaded into the buffer.", tip_value);
+                    state.buffer_loaded = true;
+                    state.out.notice(&format!("The file at {} is loaded into the buffer.", tip_value));
                     CommandResult::Nominal
                 },
                 _ => {
@@ -50,38 +76,46 @@ pub fn run_tip(state: &mut remstate::RemState, key: &str) -> CommandResult {
                 }
             }
         },
-        _ => {
+        KeyMatch::Ambiguous(keys) => {
+            CommandResult::Error(utils::describe_ambiguity("tip nickname", key, &keys))
+        },
+        KeyMatch::Missing => {
             CommandResult::Error("The tip nickname doesn't exist".to_string())
         }
     }
 }
 
 // Search the file for lines including the query
-pub fn run_grep(state: &remstate::RemState, query: &str) {
+pub fn run_grep(state: &mut remstate::RemState, query: &str) {
     let mut success: bool = false;
-    println!("Searching...");
+    let mut matches = Vec::new();
+    state.out.notice("Searching...");
     for (i, line) in state.file_loaded.lines().enumerate() {
         // Match?
         if line.to_lowercase().contains(&query.to_lowercase()) {
             // Found
-            println!("   {:5} {}", i + 1, line);
+            output::outln!(state.out, "   {:5} {}", i + 1, line);
+            matches.push(json!({ "line": i + 1, "text": line }));
             success = true;
         }
     }
+    state.out.set_data(json!(matches));
     if !success {
-        println!("I found no results in the file.");
+        state.out.notice("I found no results in the file.");
     }
 }
 
 /// Get the line of the given number
-pub fn run_line(state: &remstate::RemState, line_num: &str) -> CommandResult {
+pub fn run_line(state: &mut remstate::RemState, line_num: &str) -> CommandResult {
     match line_num.parse::<usize>() {
         Ok(linenum) => {
             if linenum < 1 || linenum > state.file_loaded.lines().count() {
                 return CommandResult::Error(format!("Enter a line number from 1 to {}", state.file_loaded.lines().count()));
             }
             // Print the line
-            println!("   {:5} {}", linenum, state.file_loaded.lines().collect::<Vec<&str>>()[linenum - 1]);
+            let line = state.file_loaded.lines().collect::<Vec<&str>>()[linenum - 1];
+            output::outln!(state.out, "   {:5} {}", linenum, line);
+            state.out.set_data(json!({ "line": linenum, "text": line }));
             CommandResult::Nominal
         },
         _ => {
@@ -91,9 +125,9 @@ pub fn run_line(state: &remstate::RemState, line_num: &str) -> CommandResult {
 }
 
 /// Append to the end of todos
-pub fn run_tda(state: &remstate::RemState, todo_string: &str) -> CommandResult {
+pub fn run_tda(state: &mut remstate::RemState, todo_string: &str) -> CommandResult {
     if utils::append_to_file(&state.config.todo_path, &format!("- {}", todo_string)) {
-        println!("Todo added successfully");
+        state.out.notice("Todo added successfully");
         CommandResult::Nominal
     } else {
         CommandResult::Error("Todo could not be added".to_string())
@@ -104,9 +138,9 @@ pub fn run_tda(state: &remstate::RemState, todo_string: &str) -> CommandResult {
 pub fn run_tdt(state: &mut remstate::RemState, count: u32) -> CommandResult {
     const TDT_MAX_ARG: u32 = 9;
     if count > TDT_MAX_ARG {
-        println!("It is unreasonable to request this many ({}) todo headers.", count);
-        println!("Please simply open the todo file in a text editor (i.e. using the 'ted' command).");
-        println!("You can configure the 'ted' command in your .remrc file.");
+        output::outln!(state.out, "It is unreasonable to request this many ({}) todo headers.", count);
+        output::outln!(state.out, "Please simply open the todo file in a text editor (i.e. using the 'ted' command).");
+        output::outln!(state.out, "You can configure the 'ted' command in your .remrc file.");
         return CommandResult::Nominal
     }
     // Get the end of todos
@@ -114,6 +148,7 @@ pub fn run_tdt(state: &mut remstate::RemState, count: u32) -> CommandResult {
         Some(contents) => {
             // Print the end of the file up until the first hash symbol
             let mut res = String::new();
+            let mut todos = Vec::new();
             let lines = contents.lines().collect::<Vec<&str>>();
             let mut headers_seen = 0;
             state.todos_ids.clear();
@@ -130,12 +165,20 @@ pub fn run_tdt(state: &mut remstate::RemState, count: u32) -> CommandResult {
                 state.todos_ids.insert(currid.clone(), i + 1);
                 // Line goes above res (because iterating in reverse)
                 res = format!("{:3}{:5} {}\n{}", currid, i + 1, lines[i], res);
+                todos.insert(0, json!({
+                    "id": currid,
+                    "line": i + 1,
+                    "text": lines[i],
+                    "header": lines[i].starts_with("##"),
+                    "completed": lines[i].contains("~~")
+                }));
                 if final_line {
                     break;
                 }
                 currid = utils::generate_next_id(currid.clone());
             }
-            println!("{}", res);
+            output::outln!(state.out, "{}", res);
+            state.out.set_data(json!(todos));
             CommandResult::Nominal
         },
         _ => {
@@ -145,7 +188,7 @@ pub fn run_tdt(state: &mut remstate::RemState, count: u32) -> CommandResult {
 }
 
 /// Clear the todo of a certain ID
-pub fn run_tdc(state: &remstate::RemState, id: &str) -> CommandResult {
+pub fn run_tdc(state: &mut remstate::RemState, id: &str) -> CommandResult {
     let linenum: usize = match state.todos_ids.get(id) {
         Some(l) => {
             *l
@@ -164,7 +207,7 @@ pub fn run_tdc(state: &remstate::RemState, id: &str) -> CommandResult {
             let target: String = lines[linenum - 1].to_string();
             let res: String = utils::strikethrough_text(&target);
             // Print successful result
-            println!("   {:5} {}", linenum, res);
+            output::outln!(state.out, "   {:5} {}", linenum, res);
             // Update the contents lines
             lines[linenum - 1] = &res;
             let mut newcontents = String::new();
@@ -182,9 +225,9 @@ pub fn run_tdc(state: &remstate::RemState, id: &str) -> CommandResult {
 }
 
 /// Edit the latest todo
-pub fn run_tde(state: &remstate::RemState, new_todo: &str) -> CommandResult {
+pub fn run_tde(state: &mut remstate::RemState, new_todo: &str) -> CommandResult {
     if utils::edit_last_line_of_file(&state.config.todo_path, &format!("- {}", new_todo), false) {
-        println!("- {}", new_todo);
+        output::outln!(state.out, "- {}", new_todo);
         CommandResult::Nominal
     } else {
         CommandResult::Error("Topmost todo could not be edited".to_string())
@@ -192,14 +235,14 @@ pub fn run_tde(state: &remstate::RemState, new_todo: &str) -> CommandResult {
 }
 
 /// Append-edit the latest todo
-pub fn run_tdae(state: &remstate::RemState, new_todo: &str) -> CommandResult {
+pub fn run_tdae(state: &mut remstate::RemState, new_todo: &str) -> CommandResult {
     // If the first char is punctuation, don't include a space between the original and appended contents
     let formatted_to_append: String = match new_todo.chars().next().unwrap_or(' ') {
         ',' | ';' | '-' | '.' | ':' => new_todo.to_string(),
         _ => format!(" {}", new_todo)
     };
     if utils::edit_last_line_of_file(&state.config.todo_path, &formatted_to_append, true) {
-        println!("Appended to the topmost todo");
+        state.out.notice("Appended to the topmost todo");
         CommandResult::Nominal
     } else {
         CommandResult::Error("Topmost todo could not be edited".to_string())
@@ -207,15 +250,15 @@ pub fn run_tdae(state: &remstate::RemState, new_todo: &str) -> CommandResult {
 }
 
 /// Append to the end of todos, prefaced by the time
-pub fn run_tdat(state: &remstate::RemState, todo_string: &str) -> CommandResult {
+pub fn run_tdat(state: &mut remstate::RemState, todo_string: &str) -> CommandResult {
     run_tda(state, &format!("{} {}", utils::get_time_formatted(), todo_string))
 }
 
 /// Start a new day as a header in the todo list
-pub fn run_tdn(state: &remstate::RemState) -> CommandResult {
+pub fn run_tdn(state: &mut remstate::RemState) -> CommandResult {
     // Append the day to the end of todos
     if utils::append_to_file(&state.config.todo_path, &format!("## {}", utils::get_date_only_formatted())) {
-        println!("New day added successfully");
+        state.out.notice("New day added successfully");
         CommandResult::Nominal
     } else {
         CommandResult::Error("Todo could not be added".to_string())
@@ -223,10 +266,10 @@ pub fn run_tdn(state: &remstate::RemState) -> CommandResult {
 }
 
 /// Open a third-party text editor with the todo file and close remslice
-pub fn run_ted(state: &remstate::RemState) -> CommandResult {
+pub fn run_ted(state: &mut remstate::RemState) -> CommandResult {
     let editor_command_prefix = &state.config.ted_command_prefix;
     let full_command = format!("{} {}", editor_command_prefix, state.config.todo_path);
-    let command_successful = utils::run_shell_command(&full_command);
+    let command_successful = run_shell_command(state, &full_command);
     if command_successful {
         CommandResult::EndProgram
     } else {
@@ -234,37 +277,618 @@ pub fn run_ted(state: &remstate::RemState) -> CommandResult {
     }
 }
 
-/// Run a shell alias
-pub fn run_al(state: &remstate::RemState, alias: &str) -> CommandResult {
-    match state.config.get_shell_alias(alias) {
-        Some(alias) => {
-            let command_successful = utils::run_shell_command(&alias.command);
-            // Only quit if successful AND desired
-            if command_successful && alias.quit_after_running {
-                CommandResult::EndProgram
-            } else {
+/// Run a command in the shell, print an informational message, and return whether successful
+fn run_shell_command(state: &mut remstate::RemState, command: &str) -> bool {
+    if utils::run_shell_command(command) {
+        state.out.notice(&format!("Command executed via {}", utils::get_shell()));
+        true
+    } else {
+        state.out.notice(&format!("Failed to execute command via {}", utils::get_shell()));
+        false
+    }
+}
+
+/// Run a shell command, wait for it to finish, and display its output and exit code
+/// The output is loaded into the buffer and can be copied, and the result is an error if the command failed
+fn run_shell_command_wait(state: &mut remstate::RemState, command: &str) -> CommandResult {
+    let output = match utils::run_shell_command_wait(command) {
+        Some(output) => output,
+        _ => return CommandResult::Error(format!("Failed to execute command via {}", utils::get_shell()))
+    };
+    for line in output.stdout.lines() {
+        output::outln!(state.out, "{}", line);
+    }
+    for line in output.stderr.lines() {
+        output::outln!(state.out, "{}", line);
+    }
+    let exit_code = output.exit_code.map(|code| code.to_string()).unwrap_or("[none]".to_string());
+    state.out.notice(&format!("Command exited with code {} via {}", exit_code, utils::get_shell()));
+    state.file_loaded = output.stdout.clone();
+    state.buffer_loaded = true;
+    if !output.stdout.trim().is_empty() {
+        state.out.set_copy_val(output.stdout.trim_end());
+    }
+    state.out.set_data(json!({
+        "stdout": output.stdout,
+        "stderr": output.stderr,
+        "exit_code": output.exit_code
+    }));
+    match output.exit_code {
+        Some(0) => CommandResult::Nominal,
+        _ => CommandResult::Error(format!("The command exited with code {}", exit_code))
+    }
+}
+
+/// Run a shell alias with arguments (see `utils::substitute_shell_args`), waiting for it to finish if `wait` is set
+/// or the alias always waits
+pub fn run_al(state: &mut remstate::RemState, key: &str, args: &[String], wait: bool) -> CommandResult {
+    match state.config.get_shell_alias(key) {
+        KeyMatch::Found(alias) => {
+            let command = match utils::substitute_shell_args(&alias.command, args) {
+                Ok(command) => command,
+                Err(descr) => return CommandResult::Error(format!("In the shell alias `{}`: {}", alias.key, descr))
+            };
+            let res = if wait || alias.wait {
+                run_shell_command_wait(state, &command)
+            } else if run_shell_command(state, &command) {
                 CommandResult::Nominal
+            } else {
+                CommandResult::Error(format!("Failed to execute command via {}", utils::get_shell()))
+            };
+            // Only quit if successful AND desired
+            match res {
+                CommandResult::Nominal if alias.quit_after_running => CommandResult::EndProgram,
+                _ => res
             }
         },
-        _ => {
+        KeyMatch::Ambiguous(keys) => {
+            CommandResult::Error(utils::describe_ambiguity("shell alias", key, &keys))
+        },
+        KeyMatch::Missing => {
             CommandResult::Error("The shell alias doesn't exist".to_string())
         }
     }
 }
 
 /// Display all aliases
-pub fn run_al_ls(state: &remstate::RemState) -> CommandResult {
-    println!("All shell aliases added:");
-    println!("{}", state.config.display_shell_aliases());
-    println!("All rem aliases added:");
-    println!("{}", state.config.display_rem_aliases());
+pub fn run_al_ls(state: &mut remstate::RemState) -> CommandResult {
+    output::outln!(state.out, "All shell aliases added:");
+    output::outln!(state.out, "{}", state.config.display_shell_aliases());
+    output::outln!(state.out, "All rem aliases added:");
+    output::outln!(state.out, "{}", state.config.display_rem_aliases());
+    let shell_aliases: Vec<_> = state.config.shell_alias_list().into_iter().map(|alias| json!({
+        "key": alias.key,
+        "command": alias.command,
+        "quit_after_running": alias.quit_after_running,
+        "wait": alias.wait
+    })).collect();
+    let rem_aliases: Vec<_> = state.config.rem_alias_list().into_iter()
+        .map(|(key, value)| json!({ "key": key, "value": value }))
+        .collect();
+    state.out.set_data(json!({ "shell_aliases": shell_aliases, "rem_aliases": rem_aliases }));
     CommandResult::Nominal
 }
 
 /// Print the current file
-pub fn run_print(state: &remstate::RemState) -> CommandResult {
+pub fn run_print(state: &mut remstate::RemState) -> CommandResult {
+    let mut lines = Vec::new();
     for (i, line) in state.file_loaded.lines().enumerate() {
-        println!("   {:5} {}", i + 1, line);
+        output::outln!(state.out, "   {:5} {}", i + 1, line);
+        lines.push(json!({ "line": i + 1, "text": line }));
+    }
+    state.out.set_data(json!(lines));
+    CommandResult::Nominal
+}
+
+/// Load a script of rem commands so that it is run after this command
+pub fn run_source(state: &mut remstate::RemState, path: &str, stop_on_error: bool) -> CommandResult {
+    match Script::load(path, stop_on_error) {
+        Some(script) => {
+            state.pending_script = Some(script);
+            CommandResult::Nominal
+        },
+        _ => {
+            CommandResult::Error("The script file could not be read".to_string())
+        }
+    }
+}
+
+/// Check the config for problems that would only show up later (i.e. tips pointing to missing files),
+/// as well as any found while loading it, and display them with where they are
+pub fn run_config_check(state: &mut remstate::RemState) -> CommandResult {
+    let config = &state.config;
+    let mut problems = state.config_problems.clone();
+    let tips: Vec<_> = config.tip_list().into_iter().zip(config.tip_origins())
+        .map(|((key, path), (_, origin))| (key, path, origin))
+        .collect();
+    for (i, (key, path, origin)) in tips.iter().enumerate() {
+        if !std::path::Path::new(path).is_file() {
+            problems.push(format!("{}: the file for tip `{}` doesn't exist: {}", origin, key, path));
+        }
+        if let Some((_, _, first_origin)) = tips[..i].iter().find(|(other, _, _)| other == key) {
+            problems.push(format!("{}: tip `{}` is already defined at {}, so this one is never used", origin, key, first_origin));
+        }
+        // Only the first definition of each key is compared, since the others are never used
+        for (j, (other, _, other_origin)) in tips.iter().enumerate() {
+            let is_first = |k: usize| !tips[..k].iter().any(|(earlier, _, _)| *earlier == tips[k].0);
+            if other != key && is_first(i) && is_first(j) && other.to_lowercase().starts_with(&key.to_lowercase()) {
+                problems.push(format!(
+                    "{}: tip `{}` is a prefix of tip `{}` (defined at {}), so `{}` can't be abbreviated to `{}`",
+                    origin, key, other, other_origin, other, key
+                ));
+            }
+        }
+    }
+    let shell_aliases = config.shell_alias_list();
+    for (i, alias) in shell_aliases.iter().enumerate() {
+        if let Some(first) = shell_aliases[..i].iter().find(|other| other.key == alias.key) {
+            problems.push(format!("{}: shell alias `{}` is already defined at {}, so this one is never used", alias.origin, alias.key, first.origin));
+        }
+    }
+    let rem_aliases = config.rem_alias_origins();
+    for (i, (key, origin)) in rem_aliases.iter().enumerate() {
+        if let Some((_, first_origin)) = rem_aliases[..i].iter().find(|(other, _)| other == key) {
+            problems.push(format!("{}: rem alias `{}` is already defined at {}, so this one is never used", origin, key, first_origin));
+        }
+        match describe_shadowed_arg_counts(key) {
+            Some(counts) if counts.is_empty() => {
+                problems.push(format!("{}: rem alias `{}` has the same name as a command, which is run instead", origin, key));
+            },
+            Some(counts) => {
+                problems.push(format!(
+                    "{}: rem alias `{}` has the same name as a command, which is run instead when called with {} arguments",
+                    origin, key, counts
+                ));
+            },
+            _ => ()
+        }
+    }
+    if let Some(origin) = config.todo_origin() {
+        if !utils::is_writable(&config.todo_path) {
+            problems.push(format!("{}: the todo file can't be written to: {}", origin, config.todo_path));
+        }
+    }
+    output::outln!(state.out, "Checked {}", state.config_location.path);
+    for problem in &problems {
+        output::outln!(state.out, "   {}", problem);
+    }
+    state.out.set_data(json!(problems));
+    match problems.len() {
+        0 => {
+            output::outln!(state.out, "No problems found");
+            CommandResult::Nominal
+        },
+        1 => CommandResult::Error("1 problem found in the config".to_string()),
+        count => CommandResult::Error(format!("{} problems found in the config", count))
     }
+}
+
+/// Add a line to the end of the config file (i.e. `tip notes ~/notes.md`) and reload the config so it takes effect
+pub fn run_config_add(state: &mut remstate::RemState, description: &str, line: &str) -> CommandResult {
+    reload_config_if_changed(state);
+    let path = state.config_location.path.clone();
+    if StructuredFormat::from_path(&path).is_some() {
+        return CommandResult::Error(format!("The config file at {} is structured (TOML or JSON), so it can only be edited by hand", path));
+    }
+    let mut edit = ConfigEdit::new(description);
+    if let Err(descr) = edit.append_line(&path, line) {
+        return CommandResult::Error(descr);
+    }
+    state.config_undo.push(edit);
+    state.reload_config();
+    state.out.notice(&format!("Added `{}` to {}", line, path));
     CommandResult::Nominal
 }
+
+/// Describe the numbers of arguments a rem alias could be called with that run a command with the same name instead
+/// (i.e. "1 or more"), as an empty string if every number does, or None if none do
+fn describe_shadowed_arg_counts(name: &str) -> Option<String> {
+    // No command limits its arguments to more than this, so any larger number behaves the same
+    const MAX_COUNT: usize = 10;
+    let shadowed: Vec<bool> = (0..=MAX_COUNT)
+        .map(|count| command_lists::get_rem_commands().iter().any(|command| command.matches(name, count as i32)))
+        .collect();
+    if shadowed.iter().all(|shadowed| *shadowed) {
+        return Some(String::new());
+    }
+    let mut ranges = Vec::new();
+    let mut count = 0;
+    while count <= MAX_COUNT {
+        if !shadowed[count] {
+            count += 1;
+            continue;
+        }
+        let start = count;
+        while count < MAX_COUNT && shadowed[count + 1] {
+            count += 1;
+        }
+        ranges.push(match count {
+            MAX_COUNT => format!("{} or more", start),
+            _ if count == start => start.to_string(),
+            _ if count == start + 1 => format!("{} or {}", start, count),
+            _ => format!("{} to {}", start, count)
+        });
+        count += 1;
+    }
+    if ranges.is_empty() {
+        None
+    } else {
+        Some(ranges.join(" or "))
+    }
+}
+
+/// Add a line defining a key (i.e. a `tip notes ...` line) to the config file, unless the key is already defined
+/// (the first definition of a key is the one used, so another one would never take effect)
+/// `get_keys` gets every key of this kind (i.e. `Config::tip_keys`)
+pub fn run_config_add_key(
+    state: &mut remstate::RemState,
+    kind: &str,
+    key: &str,
+    get_keys: fn(&Config) -> Vec<String>,
+    description: &str,
+    line: &str
+) -> CommandResult {
+    reload_config_if_changed(state);
+    if get_keys(&state.config).iter().any(|other| other == key) {
+        return CommandResult::Error(format!("The {} `{}` already exists; remove it first to replace it", kind, key));
+    }
+    run_config_add(state, description, line)
+}
+
+/// Remove every line defining a key (i.e. every `tip notes ...` line) from the config files they are in,
+/// and reload the config so it takes effect
+/// `definitions` are the keys and origins of everything of this kind (i.e. all tips), and `config_commands` are the
+/// config commands that define them (i.e. "tip")
+pub fn run_config_remove(
+    state: &mut remstate::RemState,
+    kind: &str,
+    config_commands: &[&str],
+    search_for: &str,
+    definitions: Vec<(String, String)>
+) -> CommandResult {
+    let keys: Vec<String> = definitions.iter().map(|(key, _)| key.clone()).collect();
+    let key = match utils::match_key(&keys, search_for, state.config.strict_matching) {
+        KeyMatch::Found(i) => keys[i].clone(),
+        KeyMatch::Ambiguous(keys) => return CommandResult::Error(utils::describe_ambiguity(kind, search_for, &keys)),
+        KeyMatch::Missing => return CommandResult::Error(format!("The {} `{}` doesn't exist", kind, search_for))
+    };
+    // Group the lines to remove by file
+    let mut lines_by_file: Vec<(String, Vec<usize>)> = Vec::new();
+    for (_, origin) in definitions.iter().filter(|(other, _)| *other == key) {
+        let (path, line_number) = match config_edit::parse_origin(origin) {
+            Some(location) => location,
+            _ => return CommandResult::Error(format!("The {} `{}` can only be removed from {} by hand", kind, key, origin))
+        };
+        if !config_edit::line_defines(&path, line_number, config_commands, &key) {
+            return CommandResult::Error(format!(
+                "Line {} of {} no longer defines the {} `{}`; use `reload` and try again", line_number, path, kind, key
+            ));
+        }
+        match lines_by_file.iter_mut().find(|(other, _)| *other == path) {
+            Some((_, line_numbers)) => line_numbers.push(line_number),
+            _ => lines_by_file.push((path, vec![line_number]))
+        }
+    }
+    let mut edit = ConfigEdit::new(&format!("remove {} {}", kind, key));
+    for (path, line_numbers) in &lines_by_file {
+        if let Err(descr) = edit.remove_lines(path, line_numbers) {
+            // Put back anything already removed
+            let _ = edit.undo();
+            return CommandResult::Error(descr);
+        }
+    }
+    state.config_undo.push(edit);
+    state.reload_config();
+    for (path, line_numbers) in &lines_by_file {
+        for line_number in line_numbers {
+            state.out.notice(&format!("Removed the {} `{}` from line {} of {}", kind, key, line_number, path));
+        }
+    }
+    CommandResult::Nominal
+}
+
+/// Undo the most recent change made to config files by a command (i.e. `tip-add`)
+pub fn run_config_undo(state: &mut remstate::RemState) -> CommandResult {
+    let edit = match state.config_undo.pop() {
+        Some(edit) => edit,
+        _ => return CommandResult::Error("There are no config changes to undo".to_string())
+    };
+    if let Err(descr) = edit.undo() {
+        return CommandResult::Error(descr);
+    }
+    state.reload_config();
+    state.out.notice(&format!("Undid `{}`", edit.description));
+    CommandResult::Nominal
+}
+
+/// Reload the config if its files were changed since it was loaded, so that edits are based on the files'
+/// current contents
+fn reload_config_if_changed(state: &mut remstate::RemState) {
+    if state.config_changed_on_disk() {
+        state.reload_config();
+    }
+}
+
+/// Write the fully loaded config as TOML or JSON, to a file if a path is given or otherwise as output
+pub fn run_config_export(state: &mut remstate::RemState, format_name: &str, path: Option<&str>) -> CommandResult {
+    let format = match StructuredFormat::from_name(format_name) {
+        Some(format) => format,
+        _ => return CommandResult::Error("The format must be `toml` or `json`".to_string())
+    };
+    let structured = StructuredConfig::from_config(&state.config);
+    let contents = match structured.serialize(format) {
+        Ok(contents) => contents,
+        Err(descr) => return CommandResult::Error(format!("The config could not be exported: {}", descr))
+    };
+    match path {
+        Some(path) => {
+            if !utils::write_to_file(path, &contents) {
+                return CommandResult::Error(format!("The file at {} could not be written to", path));
+            }
+            state.out.notice(&format!("Exported the config to {}", path));
+        },
+        _ => {
+            output::outln!(state.out, "{}", contents.trim_end());
+            state.out.set_data(structured.to_json());
+        }
+    }
+    CommandResult::Nominal
+}
+
+/// Display what a rem alias runs, following any rem aliases it runs down to the commands they finally run
+pub fn run_al_explain(state: &mut remstate::RemState, name: &str) -> CommandResult {
+    let rem_alias_keys = state.config.rem_alias_keys();
+    let key = match utils::match_key(&rem_alias_keys, name, state.config.strict_matching) {
+        KeyMatch::Found(i) => rem_alias_keys[i].clone(),
+        KeyMatch::Ambiguous(keys) => return CommandResult::Error(utils::describe_ambiguity("rem alias", name, &keys)),
+        KeyMatch::Missing => return CommandResult::Error(format!("The rem alias `{}` doesn't exist", name))
+    };
+    let mut lines = Vec::new();
+    let data = explain_rem_alias(&state.config, &mut vec![key], &mut lines);
+    for line in lines {
+        output::outln!(state.out, "{}", line);
+    }
+    state.out.set_data(data);
+    CommandResult::Nominal
+}
+
+/// Describe what a single command run by a rem alias runs, as lines indented by depth, and return the same as data
+/// Like when it is run, the command is a built-in command if one matches it (including its number of arguments),
+/// and otherwise a rem alias
+/// `path` is the chain of rem aliases that led to this command
+fn explain_command(config: &Config, path: &mut Vec<String>, input: &str, lines: &mut Vec<String>) -> serde_json::Value {
+    let name = Config::command_name(input);
+    if command::find_command(input, command_lists::get_rem_commands()).is_some() {
+        lines.push(format!("{}{} (command)", "   ".repeat(path.len()), input));
+        return json!({ "name": name, "command": input, "kind": "command" });
+    }
+    path.push(name);
+    let res = explain_rem_alias(config, path, lines);
+    path.pop();
+    res
+}
+
+/// Describe what the last rem alias in `path` runs (see `explain_command`)
+/// `path` is the chain of rem aliases that led to this one
+fn explain_rem_alias(config: &Config, path: &mut Vec<String>, lines: &mut Vec<String>) -> serde_json::Value {
+    let name = path.last().unwrap().clone();
+    let indent = "   ".repeat(path.len() - 1);
+    let value = match config.get_rem_alias_value(&name) {
+        Some(value) => value,
+        _ => {
+            lines.push(format!("{}{} (not a command or rem alias)", indent, name));
+            return json!({ "name": name, "kind": "unrecognized" });
+        }
+    };
+    if path[..path.len() - 1].contains(&name) {
+        lines.push(format!("{}{} (cycle: {})", indent, name, path.join(" -> ")));
+        return json!({ "name": name, "kind": "cycle", "cycle": path });
+    }
+    lines.push(format!("{}{} = {}", indent, name, value));
+    let runs: Vec<serde_json::Value> = Config::commands_run_by(&value).iter()
+        .map(|input| explain_command(config, path, input, lines))
+        .collect();
+    json!({ "name": name, "kind": "rem alias", "value": value, "runs": runs })
+}
+
+/// Display the active profile and every profile in the config
+pub fn run_profile_ls(state: &mut remstate::RemState) -> CommandResult {
+    output::outln!(state.out, "The active profile is {}", state.profile.as_deref().unwrap_or("[none]"));
+    output::outln!(state.out, "All profiles in the config:");
+    for name in &state.profile_names {
+        output::outln!(state.out, "   {}", name);
+    }
+    state.out.set_data(json!({ "profile": state.profile, "profiles": state.profile_names }));
+    CommandResult::Nominal
+}
+
+/// Switch to another profile (or no profile, for "none") and display what changed in the config
+pub fn run_profile(state: &mut remstate::RemState, name: &str) -> CommandResult {
+    let profile = match name {
+        "none" => None,
+        _ => {
+            let names = state.profile_names.clone();
+            match utils::match_key(&names, name, state.config.strict_matching) {
+                KeyMatch::Found(i) => Some(names[i].clone()),
+                KeyMatch::Ambiguous(keys) => return CommandResult::Error(utils::describe_ambiguity("profile", name, &keys)),
+                KeyMatch::Missing => return CommandResult::Error(format!("There is no `[profile={}]` section in the config", name))
+            }
+        }
+    };
+    output::outln!(state.out, "Switched to {}", profile.as_deref().map(|p| format!("the `{}` profile", p)).unwrap_or("no profile".to_string()));
+    let old = config_entries(&state.config);
+    state.set_profile(profile);
+    report_config_changes(state, old);
+    output::outln!(state.out, "The todo file is {}", state.config.todo_path);
+    CommandResult::Nominal
+}
+
+/// Display all tips
+pub fn run_tip_ls(state: &mut remstate::RemState) -> CommandResult {
+    output::outln!(state.out, "All tips added:");
+    output::outln!(state.out, "{}", state.config.display_tips());
+    let tips: Vec<_> = state.config.tip_list().into_iter()
+        .map(|(key, path)| json!({ "key": key, "path": path }))
+        .collect();
+    state.out.set_data(json!(tips));
+    CommandResult::Nominal
+}
+
+/// Reload the config file from scratch and display the tips, aliases, and score prompts that were added, removed, or changed
+pub fn run_reload(state: &mut remstate::RemState) -> CommandResult {
+    let old = config_entries(&state.config);
+    state.reload_config();
+    output::outln!(state.out, "Reloaded the config file");
+    report_config_changes(state, old);
+    CommandResult::Nominal
+}
+
+/// Reload the config because it changed on disk (see the `auto_reload` config setting)
+/// This isn't a command the user ran, so what changed is displayed as notices, which don't replace what is yanked
+pub fn auto_reload(state: &mut remstate::RemState) {
+    let old = config_entries(&state.config);
+    state.reload_config();
+    state.out.notice("Reloaded the config file because it was changed");
+    for line in describe_config_changes(state, &old).0 {
+        state.out.notice(&line);
+    }
+}
+
+/// Display the tips, aliases, and score prompts that were added, removed, or changed since the config had the
+/// entries `old` (see `config_entries`)
+fn report_config_changes(state: &mut remstate::RemState, old: Vec<(&'static str, String, String)>) {
+    let (lines, changes) = describe_config_changes(state, &old);
+    for line in lines {
+        output::outln!(state.out, "{}", line);
+    }
+    state.out.set_data(changes);
+}
+
+/// Describe what changed since the config had the entries `old`, as lines of text and as data
+fn describe_config_changes(state: &remstate::RemState, old: &[(&'static str, String, String)]) -> (Vec<String>, serde_json::Value) {
+    let new = config_entries(&state.config);
+    let mut changes = Vec::new();
+    for (kind, key, value) in old {
+        match new.iter().find(|(new_kind, new_key, _)| new_kind == kind && new_key == key) {
+            Some((_, _, new_value)) if new_value != value => changes.push(("changed", *kind, key.clone())),
+            Some(_) => (),
+            _ => changes.push(("removed", *kind, key.clone()))
+        }
+    }
+    for (kind, key, _) in &new {
+        if !old.iter().any(|(old_kind, old_key, _)| old_kind == kind && old_key == key) {
+            changes.push(("added", *kind, key.clone()));
+        }
+    }
+    let mut lines = Vec::new();
+    if changes.is_empty() {
+        lines.push("No tips, aliases, or score prompts changed".to_string());
+    }
+    for (change, kind, key) in &changes {
+        lines.push(format!("   {:8} {} `{}`", change, kind, key));
+    }
+    let changes: Vec<_> = changes.into_iter()
+        .map(|(change, kind, key)| json!({ "change": change, "kind": kind, "key": key }))
+        .collect();
+    (lines, json!(changes))
+}
+
+/// List the keyed parts of a config as (kind, key, value), for comparing configs
+fn config_entries(config: &Config) -> Vec<(&'static str, String, String)> {
+    let mut entries = Vec::new();
+    for (key, path) in config.tip_list() {
+        entries.push(("tip", key, path));
+    }
+    for alias in config.shell_alias_list() {
+        entries.push(("shell alias", alias.key, format!("{} {} {}", alias.command, alias.quit_after_running, alias.wait)));
+    }
+    for (key, value) in config.rem_alias_list() {
+        entries.push(("rem alias", key, value));
+    }
+    for prompt in config.score_positive() {
+        entries.push(("positive score prompt", prompt, String::new()));
+    }
+    for prompt in config.score_negative() {
+        entries.push(("negative score prompt", prompt, String::new()));
+    }
+    entries
+}
+
+/// Set the output format (text or json)
+pub fn run_format(state: &mut remstate::RemState, format_name: &str) -> CommandResult {
+    let format = match format_name {
+        "text" => output::OutputFormat::Text,
+        "json" => output::OutputFormat::Json,
+        _ => return CommandResult::Error("The format must be `text` or `json`".to_string())
+    };
+    state.out.set_format(format);
+    output::outln!(state.out, "The output format is now {}", format_name);
+    CommandResult::Nominal
+}
+
+/// List every command, grouped by category
+pub fn run_help(state: &mut remstate::RemState) -> CommandResult {
+    let mut commands = Vec::new();
+    for category in Category::ALL {
+        output::outln!(state.out, "{}", category.title());
+        for command in command_lists::get_rem_commands() {
+            let help = match command.get_help() {
+                Some(help) if help.category == category => help,
+                _ => continue
+            };
+            let other_names = &command.names()[1..];
+            if other_names.is_empty() {
+                output::outln!(state.out, "   {} - {}", help.usage, help.description);
+            } else {
+                output::outln!(state.out, "   {} (also: {}) - {}", help.usage, other_names.join(", "), help.description);
+            }
+            commands.push(json!({
+                "names": command.names(),
+                "usage": help.usage,
+                "description": help.description,
+                "category": category.title()
+            }));
+        }
+        output::outln!(state.out);
+    }
+    output::outln!(state.out, "Enter `help {{command}}` to see how to use a command; see `README.md` for more details");
+    state.out.set_data(json!(commands));
+    CommandResult::Nominal
+}
+
+/// Display how to use a specific command
+pub fn run_help_command(state: &mut remstate::RemState, name: &str) -> CommandResult {
+    let matching: Vec<_> = command_lists::get_rem_commands().iter()
+        .filter(|command| command.names().iter().any(|s| s == name))
+        .collect();
+    if matching.is_empty() {
+        if let Some(value) = state.config.get_rem_alias_value(name) {
+            output::outln!(state.out, "`{}` is a rem alias for `{}`", name, value);
+            return CommandResult::Nominal;
+        }
+        let suggestions = suggest_commands(state, name);
+        return if suggestions.is_empty() {
+            CommandResult::Error(format!("There is no command named `{}`", name))
+        } else {
+            CommandResult::Error(format!("There is no command named `{}` (did you mean {}?)", name, suggestions.join(", ")))
+        };
+    }
+    output::outln!(state.out, "Usage:");
+    for command in &matching {
+        if let Some(help) = command.get_help() {
+            output::outln!(state.out, "   {} - {}", help.usage, help.description);
+        }
+    }
+    let other_names: Vec<&String> = matching[0].names().iter().filter(|s| *s != name).collect();
+    if !other_names.is_empty() {
+        output::outln!(state.out, "Other names: {}", other_names.iter().map(|s| s.as_str()).collect::<Vec<&str>>().join(", "));
+    }
+    CommandResult::Nominal
+}
+
+/// Get the command names and rem aliases closest to a mistyped command name
+pub fn suggest_commands(state: &remstate::RemState, name: &str) -> Vec<String> {
+    let mut candidates = command_lists::get_rem_command_names();
+    candidates.extend(state.config.rem_alias_keys());
+    utils::closest_matches(name, &candidates)
+}
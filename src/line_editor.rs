@@ -0,0 +1,90 @@
+use crate::completion::RemCompleter;
+use crate::config::Config;
+use crate::remstate::RemState;
+use crate::utils;
+use rustyline::error::ReadlineError;
+use rustyline::history::{ DefaultHistory, History };
+use rustyline::{ Cmd, CompletionType, Editor, KeyCode, KeyEvent, Modifiers };
+
+type RemEditor = Editor<RemCompleter, DefaultHistory>;
+
+/// Reads input lines for the main input loop, with line editing and persistent history when interactive
+pub struct LineEditor {
+    /// The underlying editor (None if the input is not interactive, i.e. piped in)
+    editor: Option<RemEditor>,
+    history_path: String,
+    /// The maximum history size the editor is using, to apply the config's again when it changes (i.e. on reload)
+    history_size: usize
+}
+
+impl LineEditor {
+    /// Make a new LineEditor, loading the history file (next to the config file) if it exists
+    pub fn new(config: &Config, config_path: &str) -> LineEditor {
+        let history_path = utils::get_history_path(config_path);
+        let editor = if utils::is_interactive() {
+            Self::build_editor(config, &history_path)
+        } else {
+            None
+        };
+        LineEditor {
+            editor,
+            history_path,
+            history_size: config.history_size
+        }
+    }
+
+    fn build_editor(config: &Config, history_path: &str) -> Option<RemEditor> {
+        let editor_config = rustyline::Config::builder()
+            .max_history_size(config.history_size).ok()?
+            .history_ignore_dups(true).ok()?
+            .auto_add_history(true)
+            .completion_type(CompletionType::List)
+            .build();
+        let mut editor = RemEditor::with_config(editor_config).ok()?;
+        editor.set_helper(Some(RemCompleter::new()));
+        // Up/down search through the history for lines starting with what has been typed so far
+        editor.bind_sequence(KeyEvent(KeyCode::Up, Modifiers::NONE), Cmd::HistorySearchBackward);
+        editor.bind_sequence(KeyEvent(KeyCode::Down, Modifiers::NONE), Cmd::HistorySearchForward);
+        if !history_path.is_empty() {
+            // The history file won't exist the first time, which is fine
+            let _ = editor.load_history(history_path);
+        }
+        Some(editor)
+    }
+
+    /// Get the user's input, or None if there is no more input (EOF)
+    /// The state is used to complete arguments such as tip nicknames and todo IDs
+    pub fn read_line(&mut self, state: &mut RemState) -> Option<String> {
+        // Show the active profile, if any (i.e. "work> ")
+        let prompt = format!("{}> ", state.profile.as_deref().unwrap_or_default());
+        let editor = match &mut self.editor {
+            Some(editor) => editor,
+            _ => return utils::get_user_input_line(&mut state.out, &prompt)
+        };
+        if let Some(completer) = editor.helper_mut() {
+            completer.update(state);
+        }
+        if self.history_size != state.config.history_size {
+            self.history_size = state.config.history_size;
+            let _ = editor.history_mut().set_max_len(self.history_size);
+        }
+        loop {
+            match editor.readline(&prompt) {
+                Ok(line) => {
+                    if !self.history_path.is_empty() {
+                        // Save after every line so history isn't lost if remslice is closed abruptly
+                        let _ = editor.save_history(&self.history_path);
+                    }
+                    return Some(line.trim().to_string());
+                },
+                Err(ReadlineError::Interrupted) => {
+                    // Ctrl-C discards the current line
+                    continue;
+                },
+                _ => {
+                    return None;
+                }
+            }
+        }
+    }
+}
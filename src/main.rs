@@ -10,9 +10,16 @@ mod command;
 mod remstate;
 mod feature;
 mod command_lists;
+mod cli;
+mod script;
+mod line_editor;
+mod completion;
+mod output;
+mod config_edit;
+mod structured_config;
 
 /* TODO:
-    feat: allow intaking a file as an argument, or taking flags?
+    feat: allow intaking a file as an argument?
     feat: colors with `crossterm`? `colored`?
     feat: opt-in log file of commands/responses and dates?
     feat: (if log is implemented) display size of log, uptime, etc. in remfetch
@@ -28,14 +35,66 @@ const VERSION: &str = env!("CARGO_PKG_VERSION");
 fn main() {
     // Initialize
     let rem_data = remdata::RemData::new(VERSION, "2026/02/20", true);
-    let mut rem = rem::Rem::new(rem_data.clone());
+    let cli_args = match cli::CliArgs::parse() {
+        Ok(cli_args) => cli_args,
+        Err(descr) => {
+            eprintln!("Error: {}", descr);
+            exit_with(Some(command::CommandResult::Error(descr)));
+        }
+    };
+    let mut out = output::Output::new(Box::new(output::StdoutSink));
+    if cli_args.json {
+        out.set_format(output::OutputFormat::Json);
+    }
+    // With `--check`, problems found while loading are only listed once, by `config-check`
+    out.set_notices_muted(cli_args.check);
+    let config_location = config::ConfigLocation::resolve(cli_args.config);
+    let mut rem = rem::Rem::new(rem_data.clone(), out, config_location, cli_args.profile);
 
-    // Begin the input loop immediately
+    // Run a config check, a script, or a single command and exit, if one was given
+    if cli_args.check {
+        rem.state_mut().out.set_notices_muted(false);
+        exit_with(rem.respond_to_input("config-check".to_string(), 0));
+    }
+    if let Some(path) = cli_args.script {
+        match script::Script::load(&path, !cli_args.keep_going) {
+            Some(script) => {
+                exit_with(rem.run_script(script, 0));
+            },
+            _ => {
+                let descr = format!("The script file could not be read: {}", path);
+                eprintln!("Error: {}", descr);
+                exit_with(Some(command::CommandResult::Error(descr)));
+            }
+        }
+    }
+    if let Some(command) = cli_args.command {
+        exit_with(rem.respond_to_input(command, 0));
+    }
+
+    // Begin the input loop immediately, until the input ends
+    let interactive = utils::is_interactive();
+    let mut line_editor = line_editor::LineEditor::new(&rem.state().config, &rem.state().config_location.path);
     loop {
-        let user_input = utils::get_user_input_line();
+        rem.auto_reload_config();
+        let Some(user_input) = line_editor.read_line(rem.state_mut()) else {
+            break;
+        };
+        if !interactive && user_input.is_empty() {
+            // Skip blank lines in piped input
+            continue;
+        }
         let res = rem.respond_to_input(user_input, 0);
         if let Some(command::CommandResult::EndProgram) = res {
             break;
         }
     }
 }
+
+/// Exit the program with the exit code corresponding to a command's result
+fn exit_with(res: Option<command::CommandResult>) -> ! {
+    std::process::exit(match res {
+        Some(res) => res.exit_code(),
+        None => command::EXIT_CODE_UNRECOGNIZED
+    });
+}
@@ -0,0 +1,225 @@
+use crate::command::CommandResult;
+use serde_json::{ json, Value };
+use std::io::{ stderr, stdout, Write };
+
+/// Somewhere that lines of output can be written to (i.e. the terminal)
+pub trait OutputSink {
+    /// Write a line of text (without a trailing newline)
+    fn write_line(&mut self, line: &str);
+
+    /// Write a line that must be kept apart from the output (i.e. a notice in JSON mode, so that every line of the
+    /// output is still JSON)
+    fn write_aside(&mut self, line: &str);
+
+    /// Write a prompt for input without a newline, apart from the output if `aside` is set (see `write_aside`)
+    fn write_prompt(&mut self, prompt: &str, aside: bool);
+}
+
+/// Writes output to stdout
+pub struct StdoutSink;
+
+impl OutputSink for StdoutSink {
+    fn write_line(&mut self, line: &str) {
+        println!("{}", line);
+    }
+
+    fn write_aside(&mut self, line: &str) {
+        eprintln!("{}", line);
+    }
+
+    fn write_prompt(&mut self, prompt: &str, aside: bool) {
+        if aside {
+            eprint!("{}", prompt);
+            let _ = stderr().flush();
+        } else {
+            print!("{}", prompt);
+            let _ = stdout().flush();
+        }
+    }
+}
+
+/// How command output is formatted
+#[derive(Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    /// Human-readable text
+    Text,
+    /// One JSON object per command, for use by other programs
+    Json
+}
+
+/// Where command output is written, so that it can be captured (i.e. when piping one command into another)
+pub struct Output {
+    sink: Box<dyn OutputSink>,
+    /// Buffers for output currently being captured (the innermost capture is last)
+    captures: Vec<String>,
+    /// Everything written so far by the command being recorded, if any
+    recording: Option<String>,
+    /// What should be yanked for the command being recorded, if the command chose this explicitly
+    copy_val: Option<String>,
+    format: OutputFormat,
+    /// Whether the current command's text output is being captured to be wrapped in JSON
+    json_capturing: bool,
+    /// Structured data describing the current command's output, used instead of its text in JSON mode
+    data: Option<Value>,
+    /// Whether notices are dropped instead of displayed (i.e. while loading the config for `--check`)
+    notices_muted: bool
+}
+
+impl Output {
+    /// Make a new Output that writes to the given sink
+    pub fn new(sink: Box<dyn OutputSink>) -> Output {
+        Output {
+            sink,
+            captures: Vec::new(),
+            recording: None,
+            copy_val: None,
+            format: OutputFormat::Text,
+            json_capturing: false,
+            data: None,
+            notices_muted: false
+        }
+    }
+
+    /// Get the current output format
+    pub fn format(&self) -> OutputFormat {
+        self.format
+    }
+
+    /// Set the output format
+    pub fn set_format(&mut self, format: OutputFormat) {
+        self.format = format;
+    }
+
+    /// Write a line of output
+    pub fn println(&mut self, line: &str) {
+        if let Some(recording) = &mut self.recording {
+            recording.push_str(line);
+            recording.push('\n');
+        }
+        match self.captures.last_mut() {
+            Some(buffer) => {
+                buffer.push_str(line);
+                buffer.push('\n');
+            },
+            _ => {
+                self.sink.write_line(line);
+            }
+        }
+    }
+
+    /// Write a line to the user that isn't part of a command's output (i.e. a question, error, or status message)
+    /// This is never captured or recorded, and in JSON mode it is written aside from the JSON (to stderr)
+    pub fn notice(&mut self, line: &str) {
+        if self.notices_muted {
+            return;
+        }
+        match self.format {
+            OutputFormat::Text => self.sink.write_line(line),
+            OutputFormat::Json => self.sink.write_aside(line)
+        }
+    }
+
+    /// Set whether notices are dropped instead of displayed
+    pub fn set_notices_muted(&mut self, muted: bool) {
+        self.notices_muted = muted;
+    }
+
+    /// Show a prompt for the user's input (i.e. "> "), which like a notice is never captured or recorded
+    pub fn prompt(&mut self, prompt: &str) {
+        self.sink.write_prompt(prompt, self.format == OutputFormat::Json);
+    }
+
+    /// Start capturing all output instead of displaying it
+    pub fn begin_capture(&mut self) {
+        self.captures.push(String::new());
+    }
+
+    /// Stop the most recent capture and return everything that was written during it
+    pub fn end_capture(&mut self) -> String {
+        self.captures.pop().unwrap_or_default()
+    }
+
+    /// Start running a command: record its output so it can be yanked afterwards, and in JSON mode,
+    /// capture its output so it can be wrapped in JSON (unless it is already being captured, i.e. by a pipe)
+    pub fn begin_command(&mut self) {
+        self.recording = Some(String::new());
+        self.copy_val = None;
+        self.data = None;
+        self.json_capturing = self.format == OutputFormat::Json && self.captures.is_empty();
+        if self.json_capturing {
+            self.begin_capture();
+        }
+    }
+
+    /// Describe the current command's output as structured data, which is displayed instead of its text in JSON mode
+    pub fn set_data(&mut self, data: Value) {
+        self.data = Some(data);
+    }
+
+    /// Choose exactly what should be yanked for the command being recorded, instead of its full output
+    pub fn set_copy_val(&mut self, copy_val: &str) {
+        self.copy_val = Some(copy_val.to_string());
+    }
+
+    /// Finish running a command and return what should be yanked for it, if it output anything
+    /// In JSON mode, the command's output is displayed as JSON (errors are displayed separately by `error`)
+    pub fn end_command(&mut self, input: &str, res: &Option<CommandResult>) -> Option<String> {
+        if self.json_capturing {
+            self.json_capturing = false;
+            let text = self.end_capture();
+            let status = match res {
+                Some(CommandResult::Nominal) => Some("ok"),
+                Some(CommandResult::EndProgram) => Some("exit"),
+                _ => None
+            };
+            if let Some(status) = status {
+                let mut obj = json!({ "command": input, "status": status });
+                match self.data.take() {
+                    Some(data) => obj["data"] = data,
+                    _ => obj["output"] = json!(text.trim_end())
+                }
+                self.sink.write_line(&obj.to_string());
+            }
+        }
+        let recording = self.recording.take().unwrap_or_default();
+        match self.copy_val.take() {
+            Some(copy_val) => Some(copy_val),
+            _ if !recording.is_empty() => Some(recording.trim_end().to_string()),
+            _ => None
+        }
+    }
+}
+
+impl Output {
+    /// Display an error from a command
+    pub fn error(&mut self, input: &str, descr: &str) {
+        match self.format {
+            OutputFormat::Text => self.notice(&format!("Error: {}", descr)),
+            OutputFormat::Json => {
+                let obj = json!({ "command": input, "status": "error", "error": descr });
+                self.sink.write_line(&obj.to_string());
+            }
+        }
+    }
+
+    /// Display that a command was not recognized, along with the closest command names, if any
+    pub fn unrecognized(&mut self, input: &str, suggestions: &[String]) {
+        match self.format {
+            OutputFormat::Text if suggestions.is_empty() => self.notice("?"),
+            OutputFormat::Text => self.notice(&format!("? (did you mean {}?)", suggestions.join(", "))),
+            OutputFormat::Json => {
+                let obj = json!({ "command": input, "status": "unrecognized", "suggestions": suggestions });
+                self.sink.write_line(&obj.to_string());
+            }
+        }
+    }
+}
+
+/// Write a formatted line of output, like `println!`
+/// Ex. `output::outln!(state.out, "pong (x{})", state.ping_count)`
+macro_rules! outln {
+    ($out:expr) => ($out.println(""));
+    ($out:expr, $($arg:tt)*) => ($out.println(&format!($($arg)*)));
+}
+
+pub(crate) use outln;
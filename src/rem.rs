@@ -2,59 +2,245 @@ use crate::remdata;
 use crate::command;
 use crate::remstate;
 use crate::command_lists;
+use crate::script;
+use crate::feature;
+use crate::output::Output;
+use crate::config::ConfigLocation;
+use crate::utils::{ self, ChainOp, KeyMatch };
 
 /// Stores state and runs commands from user input
 pub struct Rem {
-    state: remstate::RemState
+    state: remstate::RemState,
+    /// The names of the rem aliases currently being run, outermost first
+    alias_stack: Vec<String>
 }
 
 impl Rem {
-    pub fn new(rem_data: remdata::RemData) -> Rem {
+    /// Make a new Rem with the config loaded from `config_location` (using `profile`, if any),
+    /// writing all output to `out`
+    pub fn new(rem_data: remdata::RemData, out: Output, config_location: ConfigLocation, profile: Option<String>) -> Rem {
         Rem {
-            state: remstate::RemState::new(rem_data)
+            state: remstate::RemState::new(rem_data, out, config_location, profile),
+            alias_stack: Vec::new()
+        }
+    }
+
+    /// Get the current state
+    pub fn state(&self) -> &remstate::RemState {
+        &self.state
+    }
+
+    /// Get the current state to change it (i.e. to read input through its output)
+    pub fn state_mut(&mut self) -> &mut remstate::RemState {
+        &mut self.state
+    }
+
+    /// Reload the config if auto-reloading is enabled and the config file has been modified since it was loaded
+    pub fn auto_reload_config(&mut self) {
+        if self.state.config.auto_reload && self.state.config_changed_on_disk() {
+            feature::auto_reload(&mut self.state);
         }
     }
 
     /// Respond to a raw user-inputted string and return whether the program should quit
+    /// The input may be a chain of several commands (i.e. "tdn; tda standup && tdt")
     pub fn respond_to_input(&mut self, input: String, recursion_level: i32) -> Option<command::CommandResult> {
         // Ensure we aren't in an infinite loop
         const MAX_RECURSION_LEVEL: i32 = 100;
         if recursion_level > MAX_RECURSION_LEVEL {
-            println!("Infinitely recursive command encountered (recursed over {MAX_RECURSION_LEVEL} times)");
+            self.state.out.notice(&format!("Infinitely recursive command encountered (recursed over {MAX_RECURSION_LEVEL} times)"));
+            if let Some(cycle) = self.alias_cycle() {
+                self.state.out.notice(&format!("The rem aliases run each other forever: {}", cycle.join(" -> ")));
+            }
             return None
         }
+        let chain = utils::split_command_chain(&input);
+        if chain.len() == 1 {
+            return self.respond_to_command(input, recursion_level);
+        }
+        let mut last_res = Some(command::CommandResult::Nominal);
+        for (op, part) in chain {
+            let succeeded = matches!(last_res, Some(command::CommandResult::Nominal));
+            let should_run = match op {
+                ChainOp::Always => true,
+                ChainOp::IfSuccess => succeeded,
+                ChainOp::IfFailure => !succeeded
+            };
+            if !should_run || part.is_empty() {
+                continue;
+            }
+            last_res = self.respond_to_command(part, recursion_level);
+            if let Some(command::CommandResult::EndProgram) = last_res {
+                // Quitting ends the whole chain
+                break;
+            }
+        }
+        last_res
+    }
+
+    /// Respond to a single command (not a chain), which may be a pipeline (i.e. "tdt 5 | grep review")
+    fn respond_to_command(&mut self, input: String, recursion_level: i32) -> Option<command::CommandResult> {
+        let stages = utils::split_pipeline(&input);
+        if stages.len() == 1 {
+            return self.respond_to_simple_command(input, recursion_level);
+        }
+        let mut piped: Option<(String, bool)> = None;
+        let mut res = None;
+        let last_stage = stages.len() - 1;
+        for (i, stage) in stages.into_iter().enumerate() {
+            let stage = match piped.take() {
+                Some((piped, keep_buffer)) => self.pipe_into(stage, piped, keep_buffer),
+                _ => stage
+            };
+            if i != last_stage {
+                self.state.out.begin_capture();
+            }
+            self.state.buffer_loaded = false;
+            res = self.respond_to_simple_command(stage, recursion_level);
+            if i != last_stage {
+                // A stage that loaded the buffer itself (i.e. `tip`) keeps it for the next stage
+                piped = Some((self.state.out.end_capture(), self.state.buffer_loaded));
+            }
+            if !matches!(res, Some(command::CommandResult::Nominal)) {
+                // A failed or quitting stage ends the pipeline
+                break;
+            }
+        }
+        res
+    }
+
+    /// Feed the output of the previous pipeline stage into a stage, returning the stage's new input
+    /// The output is loaded into the buffer (for `grep`, `line`, etc.) unless `keep_buffer` is set because the
+    /// previous stage loaded the buffer itself, and if the stage isn't a complete command on its own (i.e. `tda`),
+    /// the output is also added as its final argument
+    /// Rem aliases never get the output as an argument, since they decide their own arguments
+    fn pipe_into(&mut self, stage: String, piped: String, keep_buffer: bool) -> String {
+        let as_arg = piped.split_whitespace().collect::<Vec<&str>>().join(" ");
+        if !keep_buffer {
+            self.state.file_loaded = piped;
+        }
+        let is_rem_alias = self.state.config.get_rem_alias_value(&Self::first_arg(&stage)).is_some();
+        if as_arg.is_empty() || is_rem_alias || command::find_command(&stage, command_lists::get_rem_commands()).is_some() {
+            stage
+        } else {
+            format!("{} {}", stage, as_arg)
+        }
+    }
+
+    /// Respond to a single command with no chaining or pipes
+    fn respond_to_simple_command(&mut self, input: String, recursion_level: i32) -> Option<command::CommandResult> {
+        self.state.out.begin_command();
         let res = command::run_command(&input, &mut self.state, command_lists::get_rem_commands());
+        if let Some(copy_val) = self.state.out.end_command(&input, &res) {
+            self.state.to_copy_val = copy_val;
+        }
         match res {
             Some(command::CommandResult::Error(descr)) => {
-                println!("Error: {}", descr);
+                self.state.out.error(&input, &descr);
                 Some(command::CommandResult::Error(descr.clone()))
             },
             Some(_) => {
-                res
+                // Run any script the command asked for
+                match self.state.pending_script.take() {
+                    Some(script) => self.run_script(script, recursion_level + 1),
+                    _ => res
+                }
             },
             None => {
                 // Couldn't run the command verbatim, so check rem aliases
                 // TODO: refactor this?
                 let first_arg = Self::first_arg(&input);
-                match self.state.config.get_rem_alias_value(first_arg) {
+                match self.state.config.get_rem_alias_value(&first_arg) {
                     Some(val) => {
-                        self.run_rem_alias(&val, recursion_level + 1)
+                        // Put the arguments typed after the alias name into the alias
+                        let name_end = utils::tokenize(&input)[0].end;
+                        match utils::substitute_args(&val, &input[name_end..]) {
+                            Ok(command) => {
+                                self.run_rem_alias(&first_arg, &command, recursion_level + 1)
+                            },
+                            Err(descr) => {
+                                let descr = format!("In the rem alias `{}`: {}", first_arg, descr);
+                                self.state.out.error(&input, &descr);
+                                Some(command::CommandResult::Error(descr))
+                            }
+                        }
                     }
                     _ => {
-                        println!("?");
-                        None
+                        self.respond_to_inexact_command(input, &first_arg, recursion_level)
                     }
                 }
             }
         }
     }
 
-    fn first_arg(input: &str) -> &str {
-        input.splitn(2, ' ').collect::<Vec<&str>>()[0]
+    /// Respond to a command whose name isn't exactly a command or rem alias,
+    /// by resolving it if it's abbreviated or differently capitalized (unless matching is strict)
+    fn respond_to_inexact_command(&mut self, input: String, name: &str, recursion_level: i32) -> Option<command::CommandResult> {
+        if name.trim().is_empty() {
+            // Empty input isn't an abbreviation of anything
+            self.state.out.unrecognized(&input, &[]);
+            return None;
+        }
+        let mut candidates = command_lists::get_rem_command_names();
+        candidates.extend(self.state.config.rem_alias_keys());
+        match utils::match_key(&candidates, name, self.state.config.strict_matching) {
+            // If the name is already exact, it was used with the wrong arguments, so resolving it won't help
+            KeyMatch::Found(i) if candidates[i] != name => {
+                let name_end = utils::tokenize(&input)[0].end;
+                let resolved = format!("{}{}", candidates[i], &input[name_end..]);
+                self.respond_to_simple_command(resolved, recursion_level)
+            },
+            KeyMatch::Ambiguous(keys) => {
+                let descr = utils::describe_ambiguity("command", name, &keys);
+                self.state.out.error(&input, &descr);
+                Some(command::CommandResult::Error(descr))
+            },
+            _ => {
+                let suggestions = feature::suggest_commands(&self.state, name);
+                self.state.out.unrecognized(&input, &suggestions);
+                None
+            }
+        }
+    }
+
+    /// Run each line of a script, returning the result of the last line that was run
+    pub fn run_script(&mut self, script: script::Script, recursion_level: i32) -> Option<command::CommandResult> {
+        let mut last_res = Some(command::CommandResult::Nominal);
+        for (line_num, line) in script.lines {
+            last_res = self.respond_to_input(line, recursion_level);
+            match last_res {
+                Some(command::CommandResult::EndProgram) => {
+                    break;
+                },
+                Some(command::CommandResult::Error(_)) | None if script.stop_on_error => {
+                    self.state.out.notice(&format!("Stopped running {} after line {} failed", script.path, line_num));
+                    break;
+                },
+                _ => ()
+            }
+        }
+        last_res
+    }
+
+    fn first_arg(input: &str) -> String {
+        utils::tokenize(input).into_iter().next().map(|t| t.text).unwrap_or_default()
     }
 
     /// Run a rem alias recursively
-    fn run_rem_alias(&mut self, alias: &str, recursion_level: i32) -> Option<command::CommandResult> {
-        self.respond_to_input(alias.to_string(), recursion_level + 1)
+    fn run_rem_alias(&mut self, name: &str, alias: &str, recursion_level: i32) -> Option<command::CommandResult> {
+        self.alias_stack.push(name.to_string());
+        let res = self.respond_to_input(alias.to_string(), recursion_level + 1);
+        self.alias_stack.pop();
+        res
+    }
+
+    /// Get the first cycle of rem aliases in the ones being run (i.e. ["a", "b", "a"]), if there is one
+    fn alias_cycle(&self) -> Option<Vec<String>> {
+        for (end, name) in self.alias_stack.iter().enumerate() {
+            if let Some(start) = self.alias_stack[..end].iter().position(|other| other == name) {
+                return Some(self.alias_stack[start..=end].to_vec());
+            }
+        }
+        None
     }
 }
@@ -1,8 +1,10 @@
 use crate::remdata;
+use crate::config::ConfigLocation;
 use crate::utils;
+use serde_json::{ json, Value };
 
 /// Fetch the info
-pub fn remfetch(current_remdata: &remdata::RemData) -> String {
+pub fn remfetch(current_remdata: &remdata::RemData, config_location: &ConfigLocation, profile: Option<&str>) -> String {
     let mut res: String = String::new();
     // Like neofetch/fastfetch, but for remslice
     // Meant to look like an orange slice
@@ -14,9 +16,9 @@ pub fn remfetch(current_remdata: &remdata::RemData) -> String {
     res.push_str(&format!("                  ==  ____   O |       REM/Morning:        {}\n", current_morning));
     res.push_str(&format!("                ==____        =        Current Time:       {}\n", utils::get_date_time_formatted()));
     res.push_str(&format!("              ==]_  O  . .   /         OS:                 {}\n", utils::get_os()));
-    res.push_str(&format!("            == | ] .        =          Config Path:        {}\n", utils::get_config_path()));
-    res.push_str("          ==  |    ]  O   O/           \n");
-    res.push_str("        ==    | O    ]    =            \n");
+    res.push_str(&format!("            == | ] .        =          Config Path:        {}\n", config_location.path));
+    res.push_str(&format!("          ==  |    ]  O   O/           Config Source:      {}\n", config_location.source.describe()));
+    res.push_str(&format!("        ==    | O    ]    =            Profile:            {}\n", profile.unwrap_or("[none]")));
     res.push_str("      ==  .  |    .   ]==-             \n");
     res.push_str("    /=      |  .O   ==-                \n");
     res.push_str("     -===  |  =====-                   \n");
@@ -24,3 +26,17 @@ pub fn remfetch(current_remdata: &remdata::RemData) -> String {
     res.push_str("                                       \n");
     res
 }
+
+/// Fetch the info as structured data
+pub fn remfetch_data(current_remdata: &remdata::RemData, config_location: &ConfigLocation, profile: Option<&str>) -> Value {
+    json!({
+        "recent_version": current_remdata.get_r(),
+        "edit_date": current_remdata.get_e(),
+        "morning": current_remdata.get_m(),
+        "current_time": utils::get_date_time_formatted(),
+        "os": utils::get_os(),
+        "config_path": config_location.path,
+        "config_source": config_location.source.describe(),
+        "profile": profile
+    })
+}
@@ -1,61 +1,233 @@
 use crate::remdata;
-use crate::config::Config;
+use crate::config::{ Config, ConfigLocation };
 use crate::command;
 use crate::command_lists;
 use crate::utils;
+use crate::script::Script;
+use crate::output::Output;
+use crate::config_edit::ConfigEdit;
+use crate::structured_config::{ StructuredConfig, StructuredFormat };
 use std::collections::hash_map::HashMap;
+use std::time::SystemTime;
 
 pub struct RemState {
     pub rem_data: remdata::RemData,
     pub ping_count: u32,
     pub to_copy_val: String,
     pub file_loaded: String,
+    /// Whether the current command loaded `file_loaded` itself (i.e. `tip`), so a pipe keeps it for the next stage
+    pub buffer_loaded: bool,
     /// Store the ID (string of lowercase letters) and corresponding line NUMBER (not index)
     pub todos_ids: HashMap<String, usize>,
-    pub config: Config
+    pub config: Config,
+    /// Where the config file is loaded from
+    pub config_location: ConfigLocation,
+    /// The name of the active profile, whose `[profile=...]` sections of the config apply (None if there isn't one)
+    pub profile: Option<String>,
+    /// The name of every profile with a section in the config
+    pub profile_names: Vec<String>,
+    /// A script requested by the last command (i.e. `source`), to be run by Rem
+    pub pending_script: Option<Script>,
+    /// Where all command output is written
+    pub out: Output,
+    /// A config file requested by the last config line (i.e. `include`), to be loaded after it
+    pub pending_include: Option<String>,
+    /// Changes made to config files by commands (i.e. `tip-add`), most recent last, for `config-undo`
+    pub config_undo: Vec<ConfigEdit>,
+    /// Every problem found while loading the config (i.e. unrecognized lines), with where it is
+    pub config_problems: Vec<String>,
+    /// Every config file loaded (including included files), with when it had last been modified as of loading it
+    /// (None if it couldn't be read)
+    config_files: Vec<(String, Option<SystemTime>)>
 }
 
 impl RemState {
-    fn is_empty_or_comment(line: &str) -> bool {
+    pub fn is_empty_or_comment(line: &str) -> bool {
         line.trim().is_empty() || line.trim().chars().nth(0).unwrap() == '#'
     }
 
     fn load_config(&mut self) {
-        match utils::read_file(&utils::get_config_path()) {
-            Some(contents) => {
-                for line in contents.lines() {
-                    if Self::is_empty_or_comment(line) {
-                        continue;
-                    }
-                    let res = command::run_command(line, self, command_lists::get_config_commands());
-                    match res {
-                        Some(command::CommandResult::Error(descr)) => {
-                            println!("Configuration error in .remrc: {}", descr);
-                        },
-                        None => {
-                            println!("Configuration error in .remrc: this line was not recognized:");
-                            println!("   {}", line);
-                        },
-                        _ => ()
-                    }
+        self.config_files.clear();
+        self.config_problems.clear();
+        self.profile_names.clear();
+        let path = self.config_location.path.clone();
+        // If the file can't be read, assume it does not exist and do not bother the user, unless the user asked for
+        // this file specifically
+        if !self.load_config_file(&path, &mut Vec::new()) && self.config_location.is_explicit() {
+            self.report_config_problem(&path, "the config file could not be read");
+        }
+        if let Some(profile) = &self.profile {
+            if !self.profile_names.contains(profile) {
+                let descr = format!("there is no `[profile={}]` section", profile);
+                self.report_config_problem(&path, &descr);
+            }
+        }
+        // Catch rem aliases that would run each other forever now, rather than when they're run
+        let rem_aliases = self.config.rem_alias_origins();
+        for cycle in self.config.find_rem_alias_cycles(command_lists::get_rem_commands()) {
+            let origin = rem_aliases.iter().find(|(key, _)| *key == cycle[0]).map(|(_, origin)| origin.clone()).unwrap_or_default();
+            self.report_config_problem(&origin, &format!("rem aliases run each other forever: {}", cycle.join(" -> ")));
+        }
+    }
+
+    /// Display a problem found while loading the config and keep it for `config-check`
+    /// `origin` is where the problem is (i.e. "/home/me/.remrc:12")
+    fn report_config_problem(&mut self, origin: &str, descr: &str) {
+        self.out.notice(&format!("Configuration error in {}: {}", origin, descr));
+        self.config_problems.push(format!("{}: {}", origin, descr));
+    }
+
+    /// Run each line of a config file (and the files it includes) and return whether the file could be read
+    /// `include_stack` holds the files currently being loaded, so that a file including itself is caught
+    fn load_config_file(&mut self, path: &str, include_stack: &mut Vec<String>) -> bool {
+        self.config_files.push((path.to_string(), utils::get_modified_time(path)));
+        let contents = match utils::read_file(path) {
+            Some(contents) => contents,
+            _ => return false
+        };
+        if let Some(format) = StructuredFormat::from_path(path) {
+            self.load_structured_config(path, &contents, format);
+            return true;
+        }
+        include_stack.push(utils::canonicalize_path(path));
+        // Whether the lines are in a section (i.e. `[os=windows]`) that doesn't apply to this machine
+        let mut skipping_section = false;
+        for (i, line) in contents.lines().enumerate() {
+            if Self::is_empty_or_comment(line) {
+                continue;
+            }
+            let origin = format!("{}:{}", path, i + 1);
+            if let Some(header) = Self::parse_section_header(line) {
+                match self.section_applies(header) {
+                    Ok(applies) => skipping_section = !applies,
+                    Err(descr) => self.report_config_problem(&origin, &descr)
+                }
+                continue;
+            }
+            if skipping_section {
+                continue;
+            }
+            self.config.set_origin(&origin);
+            let res = command::run_command(line, self, command_lists::get_config_commands());
+            match res {
+                Some(command::CommandResult::Error(descr)) => {
+                    self.report_config_problem(&origin, &descr);
+                },
+                None => {
+                    self.report_config_problem(&origin, &format!("this line was not recognized: `{}`", line.trim()));
+                },
+                _ => ()
+            }
+            if let Some(include_path) = self.pending_include.take() {
+                self.include_config_file(&origin, path, &include_path, include_stack);
+            }
+        }
+        include_stack.pop();
+        true
+    }
+
+    /// Load a TOML or JSON config file (an alternative to the .remrc format)
+    fn load_structured_config(&mut self, path: &str, contents: &str, format: StructuredFormat) {
+        self.config.set_origin(path);
+        match StructuredConfig::parse(contents, format) {
+            Ok(structured) => {
+                for descr in structured.apply(&mut self.config) {
+                    self.report_config_problem(path, &descr);
                 }
             },
-            _ => {
-                // Failed to load file
-                // In this case, assume the file does not exist and do not bother the user
+            Err(descr) => {
+                self.report_config_problem(path, &descr);
             }
+        }
+    }
+
+    /// Load a config file requested by an `include` line in another config file
+    fn include_config_file(&mut self, origin: &str, including_path: &str, include_path: &str, include_stack: &mut Vec<String>) {
+        // Relative paths are relative to the including file, not the working directory
+        let include_path = match std::path::Path::new(including_path).parent() {
+            Some(dir) => dir.join(include_path).into_os_string().into_string().unwrap_or(include_path.to_string()),
+            _ => include_path.to_string()
+        };
+        let canonical = utils::canonicalize_path(&include_path);
+        if include_stack.contains(&canonical) {
+            let mut cycle = include_stack.clone();
+            cycle.push(canonical);
+            self.report_config_problem(origin, &format!("files include each other: {}", cycle.join(" -> ")));
+        } else if !self.load_config_file(&include_path, include_stack) {
+            self.report_config_problem(origin, &format!("the included file at {} could not be read", include_path));
+        }
+    }
+
+    /// Get the inside of a section header line (i.e. "os=windows" for "[os=windows]"), if the line is one
+    pub fn parse_section_header(line: &str) -> Option<&str> {
+        line.trim().strip_prefix('[')?.strip_suffix(']').map(|header| header.trim())
+    }
+
+    /// Whether the lines in a section apply to this machine and the active profile
+    /// `[os=...]`, `[host=...]`, and `[profile=...]` sections can list several comma-separated values,
+    /// and `[end]` ends a section
+    fn section_applies(&mut self, header: &str) -> Result<bool, String> {
+        if header == "end" {
+            return Ok(true);
+        }
+        let (key, values) = header.split_once('=').unwrap_or((header, ""));
+        let values: Vec<&str> = values.split(',').map(|value| value.trim()).collect();
+        let current = match key.trim() {
+            "os" => utils::get_os(),
+            "host" => utils::get_hostname(),
+            "profile" => {
+                for name in &values {
+                    if !self.profile_names.iter().any(|other| other == name) {
+                        self.profile_names.push(name.to_string());
+                    }
+                }
+                match &self.profile {
+                    Some(profile) => profile.clone(),
+                    _ => return Ok(false)
+                }
+            },
+            _ => return Err(format!("the section `[{}]` must be `[os=...]`, `[host=...]`, `[profile=...]`, or `[end]`", header))
         };
+        Ok(values.iter().any(|value| value.eq_ignore_ascii_case(&current)))
+    }
+
+    /// Switch to another profile (or no profile), reloading the config so that its sections apply
+    pub fn set_profile(&mut self, profile: Option<String>) {
+        self.profile = profile;
+        self.reload_config();
+    }
+
+    /// Rebuild the config from scratch by loading the config file again
+    pub fn reload_config(&mut self) {
+        self.config = Config::new();
+        self.load_config();
+    }
+
+    /// Whether any config file (including included files) has been modified (or created or deleted) since it was loaded
+    pub fn config_changed_on_disk(&self) -> bool {
+        self.config_files.iter().any(|(path, modified)| utils::get_modified_time(path) != *modified)
     }
 
-    /// Return a new RemState with the config loaded
-    pub fn new(rem_data: remdata::RemData) -> RemState {
+    /// Return a new RemState with the config loaded from `config_location`, writing all output to `out`
+    /// `profile` is the profile to start with, if any
+    pub fn new(rem_data: remdata::RemData, out: Output, config_location: ConfigLocation, profile: Option<String>) -> RemState {
         let mut res = RemState {
             rem_data,
             ping_count: 0,
             to_copy_val: "[empty]".to_string(),
             file_loaded: String::new(),
+            buffer_loaded: false,
             todos_ids: HashMap::new(),
-            config: Config::new()
+            config: Config::new(),
+            config_location,
+            profile,
+            profile_names: Vec::new(),
+            pending_script: None,
+            out,
+            pending_include: None,
+            config_problems: Vec::new(),
+            config_undo: Vec::new(),
+            config_files: Vec::new()
         };
         res.load_config();
         res
@@ -0,0 +1,27 @@
+use crate::remstate;
+use crate::utils;
+
+/// Stores a file of rem commands to be run line by line
+pub struct Script {
+    pub path: String,
+    /// The line NUMBER (not index) and contents of each line that should be run
+    pub lines: Vec<(usize, String)>,
+    /// Whether to stop running the rest of the script once a line fails
+    pub stop_on_error: bool
+}
+
+impl Script {
+    /// Load a script from a file, skipping empty lines and comments (like in .remrc)
+    pub fn load(path: &str, stop_on_error: bool) -> Option<Script> {
+        let contents = utils::read_file(path)?;
+        let lines = contents.lines().enumerate()
+            .filter(|(_i, line)| !remstate::RemState::is_empty_or_comment(line))
+            .map(|(i, line)| (i + 1, line.trim().to_string()))
+            .collect();
+        Some(Script {
+            path: path.to_string(),
+            lines,
+            stop_on_error
+        })
+    }
+}
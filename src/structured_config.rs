@@ -0,0 +1,178 @@
+use crate::config::Config;
+use serde::{ Deserialize, Serialize };
+
+/// A file format for structured configs (an alternative to the line-based .remrc format)
+#[derive(Clone, Copy)]
+pub enum StructuredFormat {
+    Toml,
+    Json
+}
+
+impl StructuredFormat {
+    /// Get the format with a name (i.e. "toml"), if any
+    pub fn from_name(name: &str) -> Option<StructuredFormat> {
+        match name.to_lowercase().as_str() {
+            "toml" => Some(StructuredFormat::Toml),
+            "json" => Some(StructuredFormat::Json),
+            _ => None
+        }
+    }
+
+    /// Get the format of a config file from its extension (None for .remrc-style files)
+    pub fn from_path(path: &str) -> Option<StructuredFormat> {
+        let extension = std::path::Path::new(path).extension()?.to_str()?;
+        Self::from_name(extension)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Tip {
+    key: String,
+    path: String
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ShellAlias {
+    key: String,
+    command: String,
+    #[serde(default)]
+    quit_after_running: bool,
+    #[serde(default)]
+    wait: bool
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RemAlias {
+    key: String,
+    command: String
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct Score {
+    positive: Vec<String>,
+    negative: Vec<String>,
+    divide_by: Option<f32>,
+    formula_number: Option<String>
+}
+
+/// A config in a structured form, for exporting to and importing from TOML or JSON
+/// Settings that are left out of an imported file keep their defaults
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct StructuredConfig {
+    // Plain values come before lists, since TOML requires values to come before tables
+    todo_path: Option<String>,
+    ted_command_prefix: Option<String>,
+    history_size: Option<usize>,
+    strict_matching: Option<bool>,
+    auto_reload: Option<bool>,
+    tips: Vec<Tip>,
+    shell_aliases: Vec<ShellAlias>,
+    rem_aliases: Vec<RemAlias>,
+    score: Score
+}
+
+impl StructuredConfig {
+    /// Make a structured config from everything in a loaded config
+    /// Values are already expanded, so any `$` in the values that are expanded again by `apply` is escaped as `$$`
+    pub fn from_config(config: &Config) -> StructuredConfig {
+        let escape = |value: &str| value.replace('$', "$$");
+        StructuredConfig {
+            todo_path: Some(escape(&config.todo_path)),
+            ted_command_prefix: Some(escape(&config.ted_command_prefix)),
+            history_size: Some(config.history_size),
+            strict_matching: Some(config.strict_matching),
+            auto_reload: Some(config.auto_reload),
+            tips: config.tip_list().into_iter()
+                .map(|(key, path)| Tip { key, path: escape(&path) })
+                .collect(),
+            shell_aliases: config.shell_alias_list().into_iter()
+                .map(|alias| ShellAlias {
+                    key: alias.key,
+                    command: escape(&alias.command),
+                    quit_after_running: alias.quit_after_running,
+                    wait: alias.wait
+                })
+                .collect(),
+            rem_aliases: config.rem_alias_list().into_iter()
+                .map(|(key, command)| RemAlias { key, command })
+                .collect(),
+            score: Score {
+                positive: config.score_positive(),
+                negative: config.score_negative(),
+                divide_by: Some(config.score_divby),
+                formula_number: Some(config.score_formula_number.clone())
+            }
+        }
+    }
+
+    /// Parse a structured config from the contents of a file
+    pub fn parse(contents: &str, format: StructuredFormat) -> Result<StructuredConfig, String> {
+        match format {
+            StructuredFormat::Toml => toml::from_str(contents).map_err(|e| format!("invalid TOML: {}", e.message())),
+            StructuredFormat::Json => serde_json::from_str(contents).map_err(|e| format!("invalid JSON: {}", e))
+        }
+    }
+
+    /// Write the structured config in a format
+    pub fn serialize(&self, format: StructuredFormat) -> Result<String, String> {
+        match format {
+            StructuredFormat::Toml => toml::to_string_pretty(self).map_err(|e| e.to_string()),
+            StructuredFormat::Json => serde_json::to_string_pretty(self).map_err(|e| e.to_string())
+        }
+    }
+
+    /// Get the structured config as a JSON value (i.e. for `--json` output)
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or_default()
+    }
+
+    /// Add everything in the structured config to a config, expanding variables and `~` in the same values as
+    /// a .remrc file would (see `Config::expand`), and return the problems found
+    pub fn apply(self, config: &mut Config) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut expand = |config: &Config, value: &str| match config.expand(value) {
+            Ok(expanded) => Some(expanded),
+            Err(descr) => {
+                problems.push(descr);
+                None
+            }
+        };
+        if let Some(path) = self.todo_path.and_then(|path| expand(config, &path)) {
+            config.set_todo_path(&path);
+        }
+        if let Some(prefix) = self.ted_command_prefix.and_then(|prefix| expand(config, &prefix)) {
+            config.ted_command_prefix = prefix;
+        }
+        config.history_size = self.history_size.unwrap_or(config.history_size);
+        config.strict_matching = self.strict_matching.unwrap_or(config.strict_matching);
+        config.auto_reload = self.auto_reload.unwrap_or(config.auto_reload);
+        for tip in self.tips {
+            if let Some(path) = expand(config, &tip.path) {
+                config.add_tip(&tip.key, &path);
+            }
+        }
+        for alias in self.shell_aliases {
+            let command = config.expand_shell_command(&alias.command);
+            config.add_shell_alias(&alias.key, &command, alias.quit_after_running, alias.wait);
+        }
+        for alias in self.rem_aliases {
+            config.add_rem_alias(&alias.key, &alias.command);
+        }
+        for prompt in self.score.positive {
+            config.add_score_factor(prompt, true);
+        }
+        for prompt in self.score.negative {
+            config.add_score_factor(prompt, false);
+        }
+        config.score_divby = self.score.divide_by.unwrap_or(config.score_divby);
+        if let Some(formula_number) = self.score.formula_number {
+            config.score_formula_number = formula_number;
+        }
+        problems
+    }
+}
@@ -1,35 +1,46 @@
 // Utilities for remslice
 
-use std::io::{stdin, stdout, Write};
+use crate::output::Output;
+use std::io::{stdin, IsTerminal, Write};
 use std::env;
 use std::fs;
 use std::env::consts::OS;
 use std::process::Command;
+use std::time::SystemTime;
 
-/// Get the user's input
-pub fn get_user_input_line() -> String {
-    print!("> ");
-    stdout().flush().unwrap();
+/// Whether input is coming from an interactive terminal (rather than being piped in from a file, etc.)
+pub fn is_interactive() -> bool {
+    stdin().is_terminal()
+}
+
+/// Get the user's input, or None if there is no more input (EOF)
+/// The prompt (i.e. "> ") is only shown when the input is interactive
+pub fn get_user_input_line(out: &mut Output, prompt: &str) -> Option<String> {
+    if is_interactive() {
+        out.prompt(prompt);
+    }
     let mut uin = String::new();
-    stdin().read_line(&mut uin).expect("ERR: failed to read line");
-    uin.trim().to_string()
+    match stdin().read_line(&mut uin) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(uin.trim().to_string())
+    }
 }
 
-/// Get a user's inputted decimal number
-pub fn get_user_input_decimal(num_min: f32, num_max: f32) -> f32 {
+/// Get a user's inputted decimal number, or None if there is no more input (EOF)
+pub fn get_user_input_decimal(out: &mut Output, num_min: f32, num_max: f32) -> Option<f32> {
     loop {
-        let uin = get_user_input_line();
+        let uin = get_user_input_line(out, "> ")?;
         let parsed = uin.parse::<f32>();
         match parsed {
             Ok(res) => {
                 if res >= num_min && res <= num_max {
-                    return res;
+                    return Some(res);
                 } else {
-                    println!("Please enter a valid number from {}..={}", num_min, num_max);
+                    out.notice(&format!("Please enter a valid number from {}..={}", num_min, num_max));
                 }
             },
             _ => {
-                println!("Please enter a valid number");
+                out.notice("Please enter a valid number");
             }
         }
     }
@@ -38,11 +49,15 @@ pub fn get_user_input_decimal(num_min: f32, num_max: f32) -> f32 {
 // TODO: printing with colors (crossterm?)
 
 /// Await for the user's enter press
-pub fn await_enter() {
-    print!("> [enter]");
-    stdout().flush().unwrap();
+/// This returns immediately if the input is not interactive, so piped commands are not consumed
+pub fn await_enter(out: &mut Output) {
+    if !is_interactive() {
+        return;
+    }
+    out.prompt("> [enter]");
     let mut uin = String::new();
-    stdin().read_line(&mut uin).expect("ERR: failed to read line");
+    // Reaching EOF is treated the same as pressing enter
+    let _ = stdin().read_line(&mut uin);
 }
 
 /// Get the current working directory
@@ -73,6 +88,16 @@ pub fn read_file(path: &str) -> Option<String> {
     fs::read_to_string(path).ok()
 }
 
+/// Get the time a file was last modified, if possible
+pub fn get_modified_time(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// Whether a file exists and can be appended to
+pub fn is_writable(path: &str) -> bool {
+    fs::OpenOptions::new().append(true).open(path).is_ok()
+}
+
 /// Append to a file given its path, if possible, and return whether successful
 pub fn append_to_file(path: &str, to_write: &str) -> bool {
     let file =  fs::OpenOptions::new().append(true).open(path);
@@ -155,40 +180,120 @@ pub fn get_os() -> String {
     OS.to_string()
 }
 
-/// Run a command in the shell, print an informational message, and return whether successful
-pub fn run_shell_command(command: &str) -> bool {
+/// Get the name of this machine
+pub fn get_hostname() -> String {
+    gethostname::gethostname().to_string_lossy().to_string()
+}
+
+/// Get the absolute form of a path with links resolved, or the path as-is if that isn't possible (i.e. it doesn't exist)
+pub fn canonicalize_path(path: &str) -> String {
+    fs::canonicalize(path).ok()
+        .and_then(|path| path.into_os_string().into_string().ok())
+        .unwrap_or(path.to_string())
+}
+
+/// Get the shell that shell commands are run in (powershell on Windows, sh elsewhere)
+pub fn get_shell() -> &'static str {
     match OS {
-        "windows" => {
-            // Use powershell
-            match Command::new("powershell").args(["-c", command]).spawn() {
-                Ok(_theres) => {
-                    println!("Command executed via powershell");
-                    true
-                },
-                _ => {
-                    println!("Failed to execute command via powershell");
-                    false
+        "windows" => "powershell",
+        _ => "sh"
+    }
+}
+
+/// Run a command in the shell and return whether it was started successfully
+pub fn run_shell_command(command: &str) -> bool {
+    Command::new(get_shell()).args(["-c", command]).spawn().is_ok()
+}
+
+/// What a shell command that was waited for printed, and how it exited
+pub struct ShellOutput {
+    pub stdout: String,
+    pub stderr: String,
+    /// The exit code (None if the command was ended by a signal)
+    pub exit_code: Option<i32>
+}
+
+/// Run a command in the shell, wait for it to finish, and return its output, or None if it couldn't be started
+pub fn run_shell_command_wait(command: &str) -> Option<ShellOutput> {
+    let output = Command::new(get_shell()).args(["-c", command]).output().ok()?;
+    Some(ShellOutput {
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        exit_code: output.status.code()
+    })
+}
+
+/// Expand variables in a config value:
+/// - `${name}` is replaced by the variable (see `get_variable`) or, failing that, the environment variable with
+///   the name, and is an error if neither exists
+/// - `$NAME` is replaced by the environment variable with the name, and is kept as-is if there is none
+/// - `$$` is replaced by `$`
+/// - `~` at the start of a path (i.e. "~/notes.md") is replaced by the home directory
+///
+/// If the value is shell code (`shell` is set), only `${name}` for a variable is replaced, and everything else with
+/// a `$` (i.e. `$PWD` or `${f}` in a loop) is left for the shell to expand when the command runs
+pub fn expand_variables(value: &str, get_variable: impl Fn(&str) -> Option<String>, shell: bool) -> Result<String, String> {
+    let chars: Vec<char> = value.chars().collect();
+    let mut res = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '$' if chars.get(i + 1) == Some(&'$') => {
+                res.push('$');
+                i += 2;
+            },
+            '$' if chars.get(i + 1) == Some(&'{') => {
+                let name_len = match chars[i + 2..].iter().position(|&c| c == '}') {
+                    Some(name_len) => name_len,
+                    _ if shell => {
+                        res.extend(&chars[i..]);
+                        break;
+                    },
+                    _ => return Err(format!("The `${{` in `{}` is never closed with `}}`", value))
+                };
+                let name: String = chars[i + 2..i + 2 + name_len].iter().collect();
+                match get_variable(&name) {
+                    Some(val) => res.push_str(&val),
+                    _ if shell => res.push_str(&format!("${{{}}}", name)),
+                    _ => match env::var(&name) {
+                        Ok(val) => res.push_str(&val),
+                        _ => return Err(format!("The variable `{}` is not defined (use `set {} ...`)", name, name))
+                    }
                 }
-            }
-        },
-        _ => {
-            // Use sh
-            match Command::new("sh").args(["-c", command]).spawn() {
-                Ok(_theres) => {
-                    println!("Command executed via sh");
-                    true
-                },
-                _ => {
-                    println!("Failed to execute command via sh");
-                    false
+                i += name_len + 3;
+            },
+            '$' if shell => {
+                res.push('$');
+                i += 1;
+            },
+            '$' => {
+                let name: String = chars[i + 1..].iter().take_while(|c| c.is_ascii_alphanumeric() || **c == '_').collect();
+                match env::var(&name) {
+                    Ok(val) if !name.is_empty() => res.push_str(&val),
+                    // Leave it for whatever uses the value (i.e. the shell)
+                    _ => res.push_str(&format!("${}", name))
+                }
+                i += name.len() + 1;
+            },
+            '~' if (i == 0 || chars[i - 1].is_whitespace())
+                && chars.get(i + 1).is_none_or(|c| *c == '/' || *c == '\\' || c.is_whitespace()) => {
+                match home::home_dir() {
+                    Some(path) if !path.as_os_str().is_empty() => res.push_str(&path.to_string_lossy()),
+                    _ => res.push('~')
                 }
+                i += 1;
+            },
+            c => {
+                res.push(c);
+                i += 1;
             }
         }
     }
+    Ok(res)
 }
 
-/// Get the current config directory (for the .remrc file)
-pub fn get_config_path() -> String {
+/// Get the path of the .remrc file in the home directory (empty if there is no home directory)
+pub fn get_home_config_path() -> String {
     // Return the home dir, plus .remrc
     match home::home_dir() {
         Some(mut path) if !path.as_os_str().is_empty() => {
@@ -201,6 +306,29 @@ pub fn get_config_path() -> String {
     }
 }
 
+/// Get the path of the remrc file in the XDG config directory ($XDG_CONFIG_HOME, or ~/.config if it isn't set),
+/// if there is a config directory
+pub fn get_xdg_config_path() -> Option<String> {
+    let config_dir = match env::var("XDG_CONFIG_HOME") {
+        Ok(dir) if !dir.is_empty() => std::path::PathBuf::from(dir),
+        _ => home::home_dir().filter(|path| !path.as_os_str().is_empty())?.join(".config")
+    };
+    config_dir.join("remslice").join("remrc").into_os_string().into_string().ok()
+}
+
+/// Get the path of the input history file, which sits next to the config file
+pub fn get_history_path(config_path: &str) -> String {
+    let config_path = std::path::PathBuf::from(config_path);
+    match config_path.parent() {
+        Some(dir) if !config_path.as_os_str().is_empty() => {
+            dir.join(".remslice_history").into_os_string().into_string().unwrap()
+        },
+        _ => {
+            String::new()
+        }
+    }
+}
+
 /// Generate a new ID based on the prior one ("a" -> "zzz")
 pub fn generate_next_id(currid: String) -> String {
     // Either increment final character or add a new one
@@ -229,16 +357,463 @@ pub fn generate_next_id(currid: String) -> String {
     res
 }
 
-/// Return the number of space-separated arguments (not including the command name) and the command name
+/// Return the number of arguments (not including the command name) and the command name
 pub fn process_input(full_input: &str) -> Option<(i32, String)> {
-    // TODO: impl ignoring spaces and keeping case within quotes, handling backslashes, etc.
-    let splitted: Vec<&str> = full_input.split(' ').collect::<Vec<&str>>();
-    match splitted.len() {
-        0 => None,
-        _ => Some(((splitted.len() as i32) - 1, splitted[0].to_string()))
+    let tokens = tokenize(full_input);
+    let command_name = tokens.first()?.text.clone();
+    Some(((tokens.len() as i32) - 1, command_name))
+}
+
+/// One whitespace-separated part of an input line (i.e. the command name or an argument)
+pub struct Token {
+    /// The text, with quotes and escapes removed
+    pub text: String,
+    /// The byte index in the input just after this token ends
+    pub end: usize
+}
+
+/// Whether a quote (`"` or `'`) is closed later in the text after it, so that a lone quote (i.e. the apostrophe in
+/// `it's`) can be treated as a literal character rather than quoting the rest of the line
+fn quote_is_closed(quote: char, rest: &str) -> bool {
+    let mut chars = rest.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            _ if c == quote => return true,
+            // Only double quotes can be escaped within double quotes
+            '\\' if quote == '"' => {
+                chars.next();
+            },
+            _ => ()
+        }
+    }
+    false
+}
+
+/// Split an input line into tokens separated by any amount of whitespace, like a shell would
+/// Double or single quotes group text with spaces into one token, and a backslash escapes the next character
+/// (except within single quotes, where everything is literal), and a quote that is never closed is literal
+/// Ex. `tip "my notes" it\'s` -> ["tip", "my notes", "it's"], and `al echo it's` -> ["al", "echo", "it's"]
+pub fn tokenize(full_input: &str) -> Vec<Token> {
+    let mut res = Vec::new();
+    // None while between tokens
+    let mut current: Option<String> = None;
+    let mut quote: Option<char> = None;
+    let mut chars = full_input.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (Some(q), _) if c == q => {
+                quote = None;
+            },
+            (None | Some('"'), '\\') => {
+                // An unfinished escape at the very end is ignored
+                if let Some((_, escaped)) = chars.next() {
+                    current.get_or_insert_with(String::new).push(escaped);
+                }
+            },
+            (None, '"' | '\'') if quote_is_closed(c, &full_input[i + 1..]) => {
+                // Quotes start a token even if they're empty
+                quote = Some(c);
+                current.get_or_insert_with(String::new);
+            },
+            (None, _) if c.is_whitespace() => {
+                if let Some(text) = current.take() {
+                    res.push(Token { text, end: i });
+                }
+            },
+            _ => {
+                // A quote that is never closed is kept as it is
+                current.get_or_insert_with(String::new).push(c);
+            }
+        }
+    }
+    if let Some(text) = current {
+        res.push(Token { text, end: full_input.len() });
+    }
+    res
+}
+
+/// Tracks whether each character of an input line is within quotes or escaped (see `tokenize`),
+/// so that lines can be split on operators without breaking up quoted text
+struct QuoteTracker {
+    quote: Option<char>,
+    escaped: bool
+}
+
+impl QuoteTracker {
+    fn new() -> QuoteTracker {
+        QuoteTracker {
+            quote: None,
+            escaped: false
+        }
+    }
+
+    /// Move past the next character (followed by `rest`), returning whether it is unquoted and unescaped
+    fn advance(&mut self, c: char, rest: &str) -> bool {
+        if self.escaped {
+            self.escaped = false;
+            return false;
+        }
+        match (self.quote, c) {
+            (Some(q), _) if c == q => self.quote = None,
+            (None | Some('"'), '\\') => self.escaped = true,
+            (None, '"' | '\'') if quote_is_closed(c, rest) => self.quote = Some(c),
+            (None, _) => return true,
+            _ => ()
+        }
+        false
     }
 }
 
+/// Quote text so that `tokenize` reads it as one token (and any `;`, `&&`, `||`, or `|` in it isn't treated as
+/// an operator), if it needs quoting
+/// Ex. `my "notes"` -> `"my \"notes\""`
+pub fn quote_if_needed(text: &str) -> String {
+    if !text.is_empty() && !text.chars().any(|c| c.is_whitespace() || "\"'\\;&|".contains(c)) {
+        return text.to_string();
+    }
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Remove the quotes around text that is entirely one quoted token (i.e. `"tdn; tdt"` -> `tdn; tdt`),
+/// so that commands containing operators can be given as a single argument
+pub fn unquote_whole(text: &str) -> String {
+    let tokens = tokenize(text);
+    let quoted = text.len() >= 2 && (text.starts_with('"') || text.starts_with('\''))
+        && text.ends_with(&text[..1]);
+    match tokens.as_slice() {
+        [token] if quoted && token.end == text.len() => token.text.clone(),
+        _ => text.to_string()
+    }
+}
+
+/// One part of a command with argument placeholders (see `substitute_args`)
+enum ArgPiece {
+    Text(String),
+    /// An argument by its number, with a default if it's optional
+    Arg(usize, Option<String>),
+    AllArgs
+}
+
+/// Put arguments into a command with placeholders (i.e. a rem alias), like a shell script
+/// `args` is the text typed after the alias name, and is put in exactly as typed for `$@` and the last argument
+/// placeholder (so that quotes and apostrophes are kept for commands that take the rest of the line, i.e. `tda`),
+/// while any earlier arguments are split and quoted again if needed (see `quote_if_needed`)
+/// If there are no placeholders, the arguments are added to the end:
+/// - `$1`, `$2`, ... are replaced by that argument, and are an error if it wasn't given
+/// - `${1:-default}` is replaced by the argument, or the default if it wasn't given (`${1:-}` makes it optional)
+/// - `$@` is replaced by all of the arguments
+/// - `$$` is replaced by `$`
+pub fn substitute_args(command: &str, args: &str) -> Result<String, String> {
+    let pieces = parse_arg_placeholders(command)?;
+    let tokens = tokenize(args);
+    // The arguments from a number on, exactly as typed
+    let rest_from = |n: usize| match n {
+        1 => args.trim(),
+        _ => args[tokens[n - 2].end..].trim()
+    };
+    let last_arg = pieces.iter().filter_map(|piece| match piece {
+        ArgPiece::Arg(n, _) => Some(*n),
+        _ => None
+    }).max();
+    let mut res = String::new();
+    for piece in &pieces {
+        match piece {
+            ArgPiece::Text(text) => res.push_str(text),
+            ArgPiece::AllArgs => res.push_str(args.trim()),
+            ArgPiece::Arg(n, default) => match (n.checked_sub(1).and_then(|i| tokens.get(i)), default) {
+                (Some(_), _) if Some(*n) == last_arg => res.push_str(rest_from(*n)),
+                (Some(token), _) => res.push_str(&quote_if_needed(&token.text)),
+                (_, Some(default)) => res.push_str(default),
+                _ => return Err(format!("argument {} is required", n))
+            }
+        }
+    }
+    let has_placeholder = pieces.iter().any(|piece| !matches!(piece, ArgPiece::Text(_)));
+    if !has_placeholder && !tokens.is_empty() {
+        res.push(' ');
+        res.push_str(args.trim());
+    }
+    Ok(res)
+}
+
+/// Split a command into text and the argument placeholders in it (see `substitute_args`)
+fn parse_arg_placeholders(command: &str) -> Result<Vec<ArgPiece>, String> {
+    let chars: Vec<char> = command.chars().collect();
+    let mut res = Vec::new();
+    let mut text = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let digits: String = chars[i + 1..].iter().take_while(|c| c.is_ascii_digit()).collect();
+        let piece = match (chars[i], chars.get(i + 1)) {
+            ('$', Some('$')) => {
+                text.push('$');
+                i += 2;
+                None
+            },
+            ('$', Some('@')) => {
+                i += 2;
+                Some(ArgPiece::AllArgs)
+            },
+            ('$', Some('{')) => {
+                let inside_len = chars[i + 2..].iter().position(|&c| c == '}')
+                    .ok_or(format!("The `${{` in `{}` is never closed with `}}`", command))?;
+                let inside: String = chars[i + 2..i + 2 + inside_len].iter().collect();
+                let (n, default) = match inside.split_once(":-") {
+                    Some((n, default)) => (n, Some(default.to_string())),
+                    _ => (inside.as_str(), None)
+                };
+                let n = n.parse::<usize>().ok().filter(|n| *n >= 1)
+                    .ok_or(format!("`${{{}}}` must be an argument number, i.e. `${{1}}` or `${{1:-default}}`", inside))?;
+                i += inside_len + 3;
+                Some(ArgPiece::Arg(n, default))
+            },
+            ('$', _) if !digits.is_empty() => {
+                i += digits.len() + 1;
+                Some(ArgPiece::Arg(digits.parse::<usize>().unwrap_or(usize::MAX), None))
+            },
+            (c, _) => {
+                text.push(c);
+                i += 1;
+                None
+            }
+        };
+        if let Some(piece) = piece {
+            res.push(ArgPiece::Text(std::mem::take(&mut text)));
+            res.push(piece);
+        }
+    }
+    res.push(ArgPiece::Text(text));
+    Ok(res)
+}
+
+/// Quote text so that the shell (see `get_shell`) reads it as one literal word, if it needs quoting
+/// Ex. `it's here` -> `'it'\''s here'` for sh, or `'it''s here'` for PowerShell
+pub fn shell_quote(text: &str) -> String {
+    if !text.is_empty() && text.chars().all(|c| c.is_alphanumeric() || "-_./:=@,+%".contains(c)) {
+        return text.to_string();
+    }
+    let escaped_quote = match get_shell() {
+        "powershell" => "''",
+        _ => "'\\''"
+    };
+    format!("'{}'", text.replace('\'', escaped_quote))
+}
+
+/// Put arguments and other values into a shell alias's command, quoted for the shell (see `shell_quote`)
+/// If there are no argument placeholders, the arguments are added to the end:
+/// - `{1}`, `{2}`, ... are replaced by that argument, and are an error if it wasn't given
+/// - `{args}` is replaced by all of the arguments
+/// - `{clip}` is replaced by the contents of the clipboard
+/// - `{date}` is replaced by the current date (i.e. 2020/01/01)
+///
+/// Anything else in braces is left as it is, for the shell
+pub fn substitute_shell_args(command: &str, args: &[String]) -> Result<String, String> {
+    let all_args = args.iter().map(|arg| shell_quote(arg)).collect::<Vec<String>>().join(" ");
+    let mut res = String::new();
+    let mut found_arg_placeholder = false;
+    let mut rest = command;
+    while let Some(start) = rest.find('{') {
+        res.push_str(&rest[..start]);
+        let inside_len = rest[start + 1..].find(['{', '}']).filter(|&len| rest[start + 1 + len..].starts_with('}'));
+        let inside = inside_len.map(|len| &rest[start + 1..start + 1 + len]).unwrap_or_default();
+        let value = match inside {
+            "args" => {
+                found_arg_placeholder = true;
+                Some(all_args.clone())
+            },
+            "clip" => {
+                let clip = paste_from_clipboard().ok_or("the clipboard could not be read for `{clip}`".to_string())?;
+                Some(shell_quote(&clip))
+            },
+            "date" => Some(shell_quote(&get_date_only_formatted())),
+            _ => match inside.parse::<usize>() {
+                Ok(n) if n >= 1 => {
+                    found_arg_placeholder = true;
+                    let arg = args.get(n - 1).ok_or(format!("argument {} is required", n))?;
+                    Some(shell_quote(arg))
+                },
+                _ => None
+            }
+        };
+        match value {
+            Some(value) => {
+                res.push_str(&value);
+                rest = &rest[start + inside.len() + 2..];
+            },
+            _ => {
+                res.push('{');
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    res.push_str(rest);
+    if !found_arg_placeholder && !args.is_empty() {
+        res.push(' ');
+        res.push_str(&all_args);
+    }
+    Ok(res)
+}
+
+/// How a command in a chain is connected to the command before it
+#[derive(Clone, Copy, PartialEq)]
+pub enum ChainOp {
+    /// `;` (or the first command): always run
+    Always,
+    /// `&&`: run only if the previous command succeeded
+    IfSuccess,
+    /// `||`: run only if the previous command failed
+    IfFailure
+}
+
+/// Split an input line into a chain of commands separated by `;`, `&&`, or `||` (unless quoted)
+/// Ex. "tdn; tda standup && tdt" -> [(Always, "tdn"), (Always, "tda standup"), (IfSuccess, "tdt")]
+pub fn split_command_chain(full_input: &str) -> Vec<(ChainOp, String)> {
+    let mut res = Vec::new();
+    let mut op = ChainOp::Always;
+    let mut current = String::new();
+    let mut quotes = QuoteTracker::new();
+    let mut chars = full_input.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if !quotes.advance(c, &full_input[i + c.len_utf8()..]) {
+            current.push(c);
+            continue;
+        }
+        let next_op = match (c, chars.peek().map(|(_, next)| *next)) {
+            (';', _) => Some(ChainOp::Always),
+            ('&', Some('&')) => Some(ChainOp::IfSuccess),
+            ('|', Some('|')) => Some(ChainOp::IfFailure),
+            _ => None
+        };
+        match next_op {
+            Some(next_op) => {
+                if next_op != ChainOp::Always {
+                    // Skip the second character of the operator
+                    chars.next();
+                }
+                res.push((op, current.trim().to_string()));
+                current.clear();
+                op = next_op;
+            },
+            _ => {
+                current.push(c);
+            }
+        }
+    }
+    res.push((op, current.trim().to_string()));
+    res
+}
+
+/// Split a single command (not a chain) into the stages of a pipeline separated by `|` (unless quoted)
+/// Ex. "tdt 5 | grep review" -> ["tdt 5", "grep review"]
+pub fn split_pipeline(full_input: &str) -> Vec<String> {
+    let mut res = Vec::new();
+    let mut current = String::new();
+    let mut quotes = QuoteTracker::new();
+    for (i, c) in full_input.char_indices() {
+        if quotes.advance(c, &full_input[i + c.len_utf8()..]) && c == '|' {
+            res.push(current.trim().to_string());
+            current.clear();
+        } else {
+            current.push(c);
+        }
+    }
+    res.push(current.trim().to_string());
+    res
+}
+
+/// The result of matching a search against keys that may be abbreviated or differently capitalized
+pub enum KeyMatch<T> {
+    Found(T),
+    /// Every key that the search could refer to
+    Ambiguous(Vec<String>),
+    Missing
+}
+
+impl<T> KeyMatch<T> {
+    /// Convert the found value, if any
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> KeyMatch<U> {
+        match self {
+            KeyMatch::Found(val) => KeyMatch::Found(f(val)),
+            KeyMatch::Ambiguous(keys) => KeyMatch::Ambiguous(keys),
+            KeyMatch::Missing => KeyMatch::Missing
+        }
+    }
+}
+
+/// Find the index of the key matching a search: an exact match if there is one, otherwise (unless strict)
+/// a match ignoring case, otherwise (unless strict) the only key starting with the search
+/// An empty search matches nothing
+pub fn match_key(keys: &[String], search_for: &str, strict: bool) -> KeyMatch<usize> {
+    if search_for.trim().is_empty() {
+        return KeyMatch::Missing;
+    }
+    if let Some(i) = keys.iter().position(|key| key == search_for) {
+        return KeyMatch::Found(i);
+    }
+    if strict {
+        return KeyMatch::Missing;
+    }
+    let search_lower = search_for.to_lowercase();
+    if let Some(i) = keys.iter().position(|key| key.to_lowercase() == search_lower) {
+        return KeyMatch::Found(i);
+    }
+    let mut starting_with: Vec<usize> = Vec::new();
+    for (i, key) in keys.iter().enumerate() {
+        // Keys defined more than once only count once
+        if key.to_lowercase().starts_with(&search_lower) && !starting_with.iter().any(|j| keys[*j] == *key) {
+            starting_with.push(i);
+        }
+    }
+    match starting_with.len() {
+        0 => KeyMatch::Missing,
+        1 => KeyMatch::Found(starting_with[0]),
+        _ => KeyMatch::Ambiguous(starting_with.into_iter().map(|i| keys[i].clone()).collect())
+    }
+}
+
+/// Describe a search that matched several keys
+/// Ex. "The tip nickname `n` is ambiguous; it could be: notes, nums"
+pub fn describe_ambiguity(kind: &str, search_for: &str, keys: &[String]) -> String {
+    format!("The {} `{}` is ambiguous; it could be: {}", kind, search_for, keys.join(", "))
+}
+
+/// Parse a yes/no setting (true/false, on/off, or yes/no)
+pub fn parse_bool(s: &str) -> Option<bool> {
+    match s.to_lowercase().as_str() {
+        "true" | "on" | "yes" => Some(true),
+        "false" | "off" | "no" => Some(false),
+        _ => None
+    }
+}
+
+/// Get the edit (Levenshtein) distance between two strings
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    // Only the previous row of the distance table is needed
+    let mut prev_row: Vec<usize> = (0..=b_chars.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, b_char) in b_chars.iter().enumerate() {
+            let substitution = prev_row[j] + if a_char == *b_char { 0 } else { 1 };
+            row.push(substitution.min(prev_row[j + 1] + 1).min(row[j] + 1));
+        }
+        prev_row = row;
+    }
+    prev_row[b_chars.len()]
+}
+
+/// Get the candidates closest to the target by edit distance (closest first), if they are close enough
+pub fn closest_matches(target: &str, candidates: &[String]) -> Vec<String> {
+    const MAX_DISTANCE: usize = 2;
+    const MAX_MATCHES: usize = 3;
+    let mut res: Vec<(usize, &String)> = candidates.iter()
+        .map(|candidate| (edit_distance(target, candidate), candidate))
+        .filter(|(distance, _candidate)| *distance <= MAX_DISTANCE)
+        .collect();
+    res.sort_by_key(|(distance, _candidate)| *distance);
+    res.into_iter().take(MAX_MATCHES).map(|(_distance, candidate)| candidate.clone()).collect()
+}
+
 macro_rules! string_vec {
     ($($x:expr),*) => (vec![$($x.to_string()),*]);
 }